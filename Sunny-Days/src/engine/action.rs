@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Action {
     Move(i32, i32),
 
@@ -10,13 +12,17 @@ pub enum Action {
     UseConsumable, // also unequip when hovering sword/shield
 
     ToggleStats,
+    SpendPoint, // on the stat highlighted in the stats panel
+    ToggleJournal,
 
     Confirm,
     Interact,
     Choice(char),
 
-    // NEW: Battle Option (1=Fight, 2=Inv, 3=Run). bool = 10s penalty active
+    // NEW: Battle Option (1=Fight, 2=Inv, 3=Run, 4=Skills). bool = 10s penalty active
     BattleOption(u8, bool), 
+    UseSkill, // the one highlighted in the skills menu
+    Cancel,   // backs out of choosing a target
 
     Quit, // Ctrl+C / Ctrl+Q
     None,
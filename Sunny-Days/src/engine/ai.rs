@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+/// How an enemy picks its move each turn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Behavior {
+    /// Attacks every turn.
+    #[default]
+    Aggressive,
+    /// Below `below` of its max HP, guards every other turn instead of attacking.
+    Defensive { below: f32 },
+    /// Heals `amount` when below `below` of its max HP, at most once every `every` turns.
+    Healer { amount: i32, below: f32, every: u32 },
+    /// Attacks; the first time it drops below `below` of its max HP it gains `attack`.
+    Enrage { below: f32, attack: i32 },
+}
+
+/// A later stage of a boss fight, entered once HP falls below `below`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Phase {
+    pub below: f32,
+    pub behavior: Behavior,
+    #[serde(default)]
+    pub attack: i32, // added to the enemy's stats when the phase starts
+    #[serde(default)]
+    pub defense: i32,
+    /// Shown as a dialogue in the middle of the battle.
+    #[serde(default)]
+    pub say: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AiDef {
+    #[serde(default)]
+    pub behavior: Behavior,
+    /// In order; each needs a lower `below` than the one before.
+    #[serde(default)]
+    pub phases: Vec<Phase>,
+}
+
+impl AiDef {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut last = 1.0;
+        for p in &self.phases {
+            if !(0.0..last).contains(&p.below) {
+                return Err(format!("phase thresholds must fall from 1.0, got {}", p.below));
+            }
+            last = p.below;
+        }
+        Ok(())
+    }
+}
+
+/// Where an enemy is in its script, saved with the battle.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AiState {
+    pub phase: usize, // phases entered so far
+    pub turn: u32,
+    pub enraged: bool,
+    pub last_heal: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyMove {
+    Attack,
+    Guard,
+    Heal(i32),
+}
+
+/// Something the AI did on its own between turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiShift {
+    Enraged,
+    Phase(usize), // index into `AiDef::phases`
+}
+
+fn below(hp: i32, max_hp: i32, fraction: f32) -> bool {
+    (hp as f32) < max_hp as f32 * fraction
+}
+
+impl AiState {
+    pub fn behavior(&self, def: &AiDef) -> Behavior {
+        match self.phase {
+            0 => def.behavior,
+            n => def.phases[n - 1].behavior,
+        }
+    }
+
+    /// Picks this turn's move and advances the turn count.
+    pub fn next_move(&mut self, def: &AiDef, hp: i32, max_hp: i32) -> EnemyMove {
+        self.turn += 1;
+        match self.behavior(def) {
+            Behavior::Aggressive | Behavior::Enrage { .. } => EnemyMove::Attack,
+            Behavior::Defensive { below: b } if below(hp, max_hp, b) && self.turn % 2 == 1 => EnemyMove::Guard,
+            Behavior::Defensive { .. } => EnemyMove::Attack,
+            Behavior::Healer { amount, below: b, every } => {
+                let ready = self.last_heal.is_none_or(|t| self.turn - t >= every);
+                if below(hp, max_hp, b) && ready {
+                    self.last_heal = Some(self.turn);
+                    EnemyMove::Heal(amount)
+                } else {
+                    EnemyMove::Attack
+                }
+            }
+        }
+    }
+
+    /// Checks phase and enrage thresholds after a turn. Returns what changed
+    /// and the stat changes to apply as (attack, defense).
+    pub fn react(&mut self, def: &AiDef, hp: i32, max_hp: i32) -> Option<(AiShift, i32, i32)> {
+        if let Some(p) = def.phases.get(self.phase)
+            && below(hp, max_hp, p.below)
+        {
+            self.phase += 1;
+            self.enraged = false;
+            return Some((AiShift::Phase(self.phase - 1), p.attack, p.defense));
+        }
+        if let Behavior::Enrage { below: b, attack } = self.behavior(def)
+            && !self.enraged
+            && below(hp, max_hp, b)
+        {
+            self.enraged = true;
+            return Some((AiShift::Enraged, attack, 0));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn healer_waits_between_heals() {
+        let def = AiDef { behavior: Behavior::Healer { amount: 5, below: 0.5, every: 3 }, phases: Vec::new() };
+        let mut ai = AiState::default();
+        let moves: Vec<EnemyMove> = (0..4).map(|_| ai.next_move(&def, 4, 10)).collect();
+        assert_eq!(moves, [EnemyMove::Heal(5), EnemyMove::Attack, EnemyMove::Attack, EnemyMove::Heal(5)]);
+    }
+
+    #[test]
+    fn phases_start_in_order_and_replace_the_behavior() {
+        let phase = |below| Phase { below, behavior: Behavior::Defensive { below: 1.0 }, attack: 2, defense: -1, say: Vec::new() };
+        let def = AiDef { behavior: Behavior::Enrage { below: 0.9, attack: 9 }, phases: vec![phase(0.5), phase(0.2)] };
+        let mut ai = AiState::default();
+
+        assert_eq!(ai.react(&def, 10, 10), None);
+        assert_eq!(ai.react(&def, 8, 10), Some((AiShift::Enraged, 9, 0)));
+        assert_eq!(ai.react(&def, 1, 10), Some((AiShift::Phase(0), 2, -1)));
+        assert_eq!(ai.react(&def, 1, 10), Some((AiShift::Phase(1), 2, -1)));
+        assert_eq!(ai.react(&def, 1, 10), None);
+        assert_eq!(ai.next_move(&def, 1, 10), EnemyMove::Guard);
+    }
+}
@@ -0,0 +1,636 @@
+use crate::engine::ai::{AiDef, AiShift, AiState, EnemyMove};
+use crate::engine::entity::Player;
+use crate::engine::item::ItemId;
+use crate::engine::npc::NpcId;
+use crate::engine::skill::SkillEffect;
+use crate::engine::status::{self, StatusEffect, StatusKind, StatusSpec};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Damage multiplier on the extra swing from a weapon held in the off hand.
+pub const OFF_HAND_POWER: f32 = 0.5;
+
+/// Anything that can stand in a battle.
+pub trait Combatant {
+    fn name(&self) -> &str;
+    fn hp(&self) -> i32;
+    fn max_hp(&self) -> i32;
+    fn attack(&self) -> i32;
+    /// After statuses such as tears.
+    fn defense(&self) -> i32;
+    fn speed(&self) -> i32;
+    fn take_damage(&mut self, amount: i32);
+    fn heal(&mut self, amount: i32);
+    fn statuses(&self) -> &[StatusEffect];
+    fn statuses_mut(&mut self) -> &mut Vec<StatusEffect>;
+    /// Statuses this combatant's landed hits may inflict.
+    fn on_hit(&self) -> Vec<StatusSpec>;
+
+    /// Picks this turn's move; the player's come from the battle menu instead.
+    fn next_move(&mut self) -> EnemyMove {
+        EnemyMove::Attack
+    }
+
+    /// Runs after every turn both sides survive; may change the combatant's stats.
+    fn react(&mut self) -> Option<AiShift> {
+        None
+    }
+
+    /// Whether a plain attack is followed by a weaker off-hand swing.
+    fn dual_wielding(&self) -> bool {
+        false
+    }
+
+    fn is_down(&self) -> bool {
+        self.hp() <= 0
+    }
+}
+
+impl Combatant for Player {
+    fn name(&self) -> &str { "You" }
+    fn hp(&self) -> i32 { self.hp }
+    fn max_hp(&self) -> i32 { Player::max_hp(self) }
+    fn attack(&self) -> i32 { Player::attack(self) }
+    fn defense(&self) -> i32 { Player::defense(self) }
+    fn speed(&self) -> i32 { Player::speed(self) }
+    fn take_damage(&mut self, amount: i32) { self.hp -= amount; }
+    fn heal(&mut self, amount: i32) { Player::heal(self, amount) }
+    fn statuses(&self) -> &[StatusEffect] { &self.statuses }
+    fn statuses_mut(&mut self) -> &mut Vec<StatusEffect> { &mut self.statuses }
+    fn on_hit(&self) -> Vec<StatusSpec> {
+        self.inventory.worn().flat_map(|eq| eq.on_hit.iter().copied()).collect()
+    }
+    fn dual_wielding(&self) -> bool { self.inventory.dual_wielding() }
+}
+
+/// Base stats for an NPC that can be fought, from `assets/data/npcs.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemyStats {
+    pub hp: i32,
+    pub attack: i32,
+    pub defense: i32,
+    pub speed: i32,
+    #[serde(default)]
+    pub on_hit: Vec<StatusSpec>,
+    #[serde(default)]
+    pub ai: AiDef,
+    /// Fight alongside this NPC whenever it's challenged.
+    #[serde(default)]
+    pub allies: Vec<Ally>,
+    #[serde(default)]
+    pub gold: u32, // paid out when the battle is won
+    #[serde(default)]
+    pub drops: Vec<LootDrop>,
+}
+
+/// A consumable from the item catalogue an enemy may leave behind.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LootDrop {
+    pub item: ItemId,
+    pub chance: f32,
+}
+
+impl EnemyStats {
+    /// XP for beating this enemy, scaled by how tough it is.
+    pub fn xp(&self) -> u32 {
+        (self.hp / 2 + self.attack + self.defense / 2 + self.speed / 2).max(1) as u32
+    }
+}
+
+/// A fighter that only exists in battle; it has no spot on the map and takes
+/// its leader's id.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ally {
+    pub name: String,
+    #[serde(flatten)]
+    pub stats: EnemyStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Enemy {
+    pub id: NpcId,
+    pub name: String,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub attack: i32,
+    pub defense: i32,
+    pub speed: i32,
+    pub on_hit: Vec<StatusSpec>,
+    pub statuses: Vec<StatusEffect>,
+    pub ai: AiDef,
+    pub ai_state: AiState,
+}
+
+impl Enemy {
+    pub fn new(id: NpcId, name: impl Into<String>, stats: &EnemyStats) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            hp: stats.hp,
+            max_hp: stats.hp,
+            attack: stats.attack,
+            defense: stats.defense,
+            speed: stats.speed,
+            on_hit: stats.on_hit.clone(),
+            statuses: Vec::new(),
+            ai: stats.ai.clone(),
+            ai_state: AiState::default(),
+        }
+    }
+}
+
+impl Combatant for Enemy {
+    fn name(&self) -> &str { &self.name }
+    fn hp(&self) -> i32 { self.hp }
+    fn max_hp(&self) -> i32 { self.max_hp }
+    fn attack(&self) -> i32 { self.attack }
+    fn defense(&self) -> i32 { self.defense + status::defense_mod(&self.statuses) }
+    fn speed(&self) -> i32 { self.speed }
+    fn take_damage(&mut self, amount: i32) { self.hp -= amount; }
+    fn heal(&mut self, amount: i32) { self.hp = (self.hp + amount).min(self.max_hp); }
+    fn statuses(&self) -> &[StatusEffect] { &self.statuses }
+    fn statuses_mut(&mut self) -> &mut Vec<StatusEffect> { &mut self.statuses }
+    fn on_hit(&self) -> Vec<StatusSpec> { self.on_hit.clone() }
+
+    fn next_move(&mut self) -> EnemyMove {
+        self.ai_state.next_move(&self.ai, self.hp, self.max_hp)
+    }
+
+    fn react(&mut self) -> Option<AiShift> {
+        let (shift, attack, defense) = self.ai_state.react(&self.ai, self.hp, self.max_hp)?;
+        self.attack += attack;
+        self.defense += defense;
+        Some(shift)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TurnChoice {
+    Fight,
+    Skill(SkillEffect), // acts when a plain attack would
+    UsedItem, // the item itself is applied by the caller before the turn
+    Flee,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Player,
+    Enemy(usize), // index into the battle's enemies
+}
+
+/// What happened during a turn, in order. The caller decides how to show it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CombatEvent {
+    Hit { attacker: Side, defender: Side, damage: i32 },
+    Deflected { attacker: Side, defender: Side }, // the defender blocked the hit
+    StatusApplied { target: Side, kind: StatusKind },
+    StatusTick { target: Side, kind: StatusKind, hp: i32 }, // hp gained (negative for damage)
+    StatusEnded { target: Side, kind: StatusKind },
+    Stunned { side: Side },
+    Healed { side: Side, hp: i32 },
+    Enraged { enemy: usize },
+    PhaseChanged { enemy: usize, phase: usize }, // index into that enemy's `ai.phases`
+    Defeated { enemy: usize },
+    Fled,
+    FleeFailed,
+    MustFinish, // can't run from a fight the player started
+}
+
+impl CombatEvent {
+    /// The battle-log line for this event; `names` are the enemies' names.
+    pub fn message(&self, names: &[String]) -> String {
+        let name = |side: &Side| match side {
+            Side::Player => "You",
+            Side::Enemy(i) => names[*i].as_str(),
+        };
+        match self {
+            CombatEvent::Hit { attacker: Side::Player, defender, damage } => {
+                format!("You hit {} for {} dmg.", name(defender), damage)
+            }
+            CombatEvent::Hit { attacker, damage, .. } => format!("{} hit you for {} dmg.", name(attacker), damage),
+            CombatEvent::Deflected { attacker: Side::Player, defender } => format!("{} deflected your attack!", name(defender)),
+            CombatEvent::Deflected { attacker, .. } => format!("You deflected {}'s attack!", name(attacker)),
+            CombatEvent::StatusApplied { target: Side::Player, kind } => format!("You are {}!", kind.afflicted()),
+            CombatEvent::StatusApplied { target, kind } => format!("{} is {}!", name(target), kind.afflicted()),
+            CombatEvent::StatusTick { target, kind, hp } => {
+                if *hp < 0 {
+                    format!("{} took {} {} dmg.", name(target), -hp, kind.label().to_lowercase())
+                } else {
+                    format!("{} recovered {} HP.", name(target), hp)
+                }
+            }
+            CombatEvent::StatusEnded { target: Side::Player, kind } => format!("Your {} wore off.", kind.label().to_lowercase()),
+            CombatEvent::StatusEnded { target, kind } => {
+                format!("{}'s {} wore off.", name(target), kind.label().to_lowercase())
+            }
+            CombatEvent::Stunned { side: Side::Player } => "You are stunned and can't act!".to_string(),
+            CombatEvent::Stunned { side } => format!("{} is stunned and can't act!", name(side)),
+            CombatEvent::Healed { side: Side::Player, hp } => format!("You healed {} HP.", hp),
+            CombatEvent::Healed { side, hp } => format!("{} healed {} HP.", name(side), hp),
+            CombatEvent::Enraged { enemy } => format!("{} flies into a rage!", names[*enemy]),
+            CombatEvent::PhaseChanged { enemy, .. } => format!("{} changes tactics!", names[*enemy]),
+            CombatEvent::Defeated { enemy } => format!("{} is defeated!", names[*enemy]),
+            CombatEvent::Fled => "You fled the battle!".to_string(),
+            CombatEvent::FleeFailed => "Failed to flee!".to_string(),
+            CombatEvent::MustFinish => "You started this, finish it!".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+    Fled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnResult {
+    pub events: Vec<CombatEvent>,
+    pub outcome: Option<Outcome>, // None while the battle goes on
+}
+
+/// Rules that carry across turns of one battle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TurnRules {
+    pub penalty_mode: bool,     // the enemies always move first
+    pub player_initiated: bool, // fleeing isn't allowed
+}
+
+pub fn calc_damage(atk: i32) -> i32 {
+    (atk as f32 * 1.2) as i32
+}
+
+pub fn deflect_chance(def: i32) -> f32 {
+    (def as f32 / 10.0) * 0.2
+}
+
+fn strike<R: Rng>(
+    attacker: &mut dyn Combatant,
+    defender: &mut dyn Combatant,
+    sides: (Side, Side), // attacker, defender
+    power: f32,
+    rng: &mut R,
+    events: &mut Vec<CombatEvent>,
+) {
+    let (attacker_side, defender_side) = sides;
+    let damage = (calc_damage(attacker.attack()) as f32 * power) as i32;
+    if status::consume_guard(defender.statuses_mut()) {
+        events.push(CombatEvent::Deflected { attacker: attacker_side, defender: defender_side });
+        return;
+    }
+    if rng.r#gen::<f32>() < deflect_chance(defender.defense()) {
+        events.push(CombatEvent::Deflected { attacker: attacker_side, defender: defender_side });
+        return;
+    }
+    defender.take_damage(damage);
+    events.push(CombatEvent::Hit { attacker: attacker_side, defender: defender_side, damage });
+    for spec in attacker.on_hit() {
+        if rng.r#gen::<f32>() < spec.chance {
+            status::apply(defender.statuses_mut(), spec.kind, spec.turns);
+            events.push(CombatEvent::StatusApplied { target: defender_side, kind: spec.kind });
+        }
+    }
+}
+
+/// Uses up a turn of stun; true if `c` loses its action.
+fn stunned(c: &mut dyn Combatant, side: Side, events: &mut Vec<CombatEvent>) -> bool {
+    let stunned = status::consume_stun(c.statuses_mut());
+    if stunned {
+        events.push(CombatEvent::Stunned { side });
+    }
+    stunned
+}
+
+/// Enemy `i`'s action, as its AI picks it.
+fn enemy_acts<R: Rng>(
+    enemy: &mut dyn Combatant,
+    i: usize,
+    player: &mut dyn Combatant,
+    rng: &mut R,
+    events: &mut Vec<CombatEvent>,
+) {
+    let side = Side::Enemy(i);
+    if stunned(enemy, side, events) {
+        return;
+    }
+    match enemy.next_move() {
+        EnemyMove::Attack => strike(enemy, player, (side, Side::Player), 1.0, rng, events),
+        EnemyMove::Guard => {
+            status::apply(enemy.statuses_mut(), StatusKind::Guard, 2);
+            events.push(CombatEvent::StatusApplied { target: side, kind: StatusKind::Guard });
+        }
+        EnemyMove::Heal(hp) => {
+            enemy.heal(hp);
+            events.push(CombatEvent::Healed { side, hp });
+        }
+    }
+}
+
+/// The player's action for a fight or skill turn, aimed at enemy `i`.
+fn player_acts<R: Rng>(
+    player: &mut dyn Combatant,
+    enemy: &mut dyn Combatant,
+    i: usize,
+    choice: TurnChoice,
+    rng: &mut R,
+    events: &mut Vec<CombatEvent>,
+) {
+    if stunned(player, Side::Player, events) {
+        return;
+    }
+    let sides = (Side::Player, Side::Enemy(i));
+    let (side, kind, turns) = match choice {
+        TurnChoice::Skill(SkillEffect::Strike { power }) => return strike(player, enemy, sides, power, rng, events),
+        TurnChoice::Skill(SkillEffect::Guard { turns }) => (Side::Player, StatusKind::Guard, turns),
+        TurnChoice::Skill(SkillEffect::Inflict(spec)) => (Side::Enemy(i), spec.kind, spec.turns),
+        _ => {
+            strike(player, enemy, sides, 1.0, rng, events);
+            if player.dual_wielding() && !enemy.is_down() {
+                strike(player, enemy, sides, OFF_HAND_POWER, rng, events);
+            }
+            return;
+        }
+    };
+    let target = if side == Side::Player { player.statuses_mut() } else { enemy.statuses_mut() };
+    status::apply(target, kind, turns);
+    events.push(CombatEvent::StatusApplied { target: side, kind });
+}
+
+/// End-of-turn ticks (poison, bleed, regen) and durations for one side.
+fn tick_statuses(c: &mut dyn Combatant, side: Side, events: &mut Vec<CombatEvent>) {
+    let ticks: Vec<(StatusKind, i32)> = c.statuses().iter()
+        .map(|s| (s.kind, s.kind.hp_per_turn() * s.stacks as i32))
+        .filter(|&(_, hp)| hp != 0)
+        .collect();
+    for (kind, hp) in ticks {
+        if c.is_down() { break; }
+        if hp < 0 { c.take_damage(-hp) } else { c.heal(hp) }
+        events.push(CombatEvent::StatusTick { target: side, kind, hp });
+    }
+    for kind in status::count_down(c.statuses_mut()) {
+        events.push(CombatEvent::StatusEnded { target: side, kind });
+    }
+}
+
+fn all_down(enemies: &[impl Combatant]) -> bool {
+    enemies.iter().all(|e| e.is_down())
+}
+
+/// Who acts this turn, fastest first. The player wins ties and, under the
+/// idle penalty, goes after every enemy.
+pub fn initiative(player: &impl Combatant, enemies: &[impl Combatant], penalty_mode: bool) -> Vec<Side> {
+    let mut order: Vec<Side> = (0..enemies.len()).filter(|&i| !enemies[i].is_down()).map(Side::Enemy).collect();
+    order.sort_by_key(|s| match s {
+        Side::Enemy(i) => -enemies[*i].speed(),
+        Side::Player => 0,
+    });
+    let at = if penalty_mode {
+        order.len()
+    } else {
+        order.iter().position(|s| matches!(s, Side::Enemy(i) if enemies[*i].speed() <= player.speed())).unwrap_or(order.len())
+    };
+    order.insert(at, Side::Player);
+    order
+}
+
+/// Every standing enemy acts, fastest first (after a used item or a failed flee).
+fn enemies_act<R: Rng>(
+    player: &mut impl Combatant,
+    enemies: &mut [impl Combatant],
+    rng: &mut R,
+    events: &mut Vec<CombatEvent>,
+) {
+    for side in initiative(player, enemies, true) {
+        if let Side::Enemy(i) = side
+            && !enemies[i].is_down()
+            && !player.is_down()
+        {
+            enemy_acts(&mut enemies[i], i, player, rng, events);
+        }
+    }
+}
+
+/// Resolves one battle turn: everyone acts in speed order, then statuses
+/// tick. The player's attack goes at `target`, or the first enemy still
+/// standing if that one is down. Only the combatants and `rng` change;
+/// nothing is logged, the returned events describe the turn.
+pub fn resolve_turn<R: Rng>(
+    player: &mut impl Combatant,
+    enemies: &mut [impl Combatant],
+    target: usize,
+    rules: TurnRules,
+    choice: TurnChoice,
+    rng: &mut R,
+) -> TurnResult {
+    let mut events = Vec::new();
+    let standing: Vec<bool> = enemies.iter().map(|e| !e.is_down()).collect();
+
+    match choice {
+        TurnChoice::Fight | TurnChoice::Skill(_) => {
+            for side in initiative(player, enemies, rules.penalty_mode) {
+                if player.is_down() || all_down(enemies) { break; }
+                match side {
+                    Side::Player => {
+                        let i = if enemies.get(target).is_some_and(|e| !e.is_down()) {
+                            target
+                        } else {
+                            enemies.iter().position(|e| !e.is_down()).unwrap_or(target)
+                        };
+                        player_acts(player, &mut enemies[i], i, choice, rng, &mut events);
+                    }
+                    Side::Enemy(i) if !enemies[i].is_down() => enemy_acts(&mut enemies[i], i, player, rng, &mut events),
+                    Side::Enemy(_) => {}
+                }
+            }
+        }
+        TurnChoice::UsedItem => enemies_act(player, enemies, rng, &mut events),
+        TurnChoice::Flee => {
+            if rules.player_initiated {
+                events.push(CombatEvent::MustFinish);
+                enemies_act(player, enemies, rng, &mut events);
+            } else if stunned(player, Side::Player, &mut events) {
+                enemies_act(player, enemies, rng, &mut events);
+            } else if rng.r#gen::<f32>() < 0.5 {
+                events.push(CombatEvent::Fled);
+                return TurnResult { events, outcome: Some(Outcome::Fled) };
+            } else {
+                events.push(CombatEvent::FleeFailed);
+                enemies_act(player, enemies, rng, &mut events);
+            }
+        }
+    }
+
+    if !all_down(enemies) && !player.is_down() {
+        tick_statuses(player, Side::Player, &mut events);
+        for (i, e) in enemies.iter_mut().enumerate() {
+            if !e.is_down() { tick_statuses(e, Side::Enemy(i), &mut events); }
+        }
+    }
+    for (i, e) in enemies.iter_mut().enumerate() {
+        if standing[i] && e.is_down() {
+            events.push(CombatEvent::Defeated { enemy: i });
+        } else if !e.is_down() && !player.is_down() {
+            match e.react() {
+                Some(AiShift::Enraged) => events.push(CombatEvent::Enraged { enemy: i }),
+                Some(AiShift::Phase(phase)) => events.push(CombatEvent::PhaseChanged { enemy: i, phase }),
+                None => {}
+            }
+        }
+    }
+
+    let outcome = if all_down(enemies) {
+        Some(Outcome::Won)
+    } else if player.is_down() {
+        Some(Outcome::Lost)
+    } else {
+        None
+    };
+    TurnResult { events, outcome }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::entity::GearSlot;
+    use crate::engine::item::ItemRegistry;
+    use crate::engine::rng::GameRng;
+
+    fn enemy(hp: i32, attack: i32, defense: i32, speed: i32) -> Enemy {
+        Enemy::new(
+            serde_json::from_str("\"dummy\"").unwrap(),
+            "Dummy",
+            &EnemyStats { hp, attack, defense, speed, on_hit: Vec::new(), ai: AiDef::default(), allies: Vec::new(), gold: 0, drops: Vec::new() },
+        )
+    }
+
+    fn player() -> Player {
+        let mut p = Player::new(0, 0);
+        p.base_defense = 0;
+        p
+    }
+
+    #[test]
+    fn faster_player_finishes_before_the_enemy_swings() {
+        let (mut p, mut e) = (player(), [enemy(1, 50, 0, -100)]);
+        let r = resolve_turn(&mut p, &mut e, 0, TurnRules::default(), TurnChoice::Fight, &mut GameRng::for_world(1));
+        assert_eq!(r.outcome, Some(Outcome::Won));
+        assert_eq!(r.events.len(), 2); // the hit, then the enemy going down
+        assert_eq!(p.hp, p.max_hp());
+    }
+
+    #[test]
+    fn penalty_lets_the_enemy_go_first() {
+        let (mut p, mut e) = (player(), [enemy(100, 5, 0, -100)]);
+        let rules = TurnRules { penalty_mode: true, ..TurnRules::default() };
+        let r = resolve_turn(&mut p, &mut e, 0, rules, TurnChoice::Fight, &mut GameRng::for_world(2));
+        assert_eq!(r.events[0], CombatEvent::Hit { attacker: Side::Enemy(0), defender: Side::Player, damage: 6 });
+    }
+
+    #[test]
+    fn off_hand_weapon_swings_again_for_half() {
+        let items = ItemRegistry::builtin();
+        let knife = || items.new_equipment(&ItemId::new("hunting_knife")).unwrap();
+        let (mut p, mut e) = (player(), [enemy(100, 0, 0, -100)]);
+        assert_eq!(p.equip(knife()).unwrap().0, GearSlot::MainHand);
+        assert_eq!(p.equip(knife()).unwrap().0, GearSlot::OffHand);
+        let r = resolve_turn(&mut p, &mut e, 0, TurnRules::default(), TurnChoice::Fight, &mut GameRng::for_world(9));
+        assert_eq!(&r.events[..2], &[
+            CombatEvent::Hit { attacker: Side::Player, defender: Side::Enemy(0), damage: 16 },
+            CombatEvent::Hit { attacker: Side::Player, defender: Side::Enemy(0), damage: 8 },
+        ]);
+    }
+
+    #[test]
+    fn high_defense_always_deflects() {
+        let (mut p, mut e) = (player(), [enemy(100, 0, 60, -100)]);
+        let r = resolve_turn(&mut p, &mut e, 0, TurnRules::default(), TurnChoice::Fight, &mut GameRng::for_world(3));
+        assert_eq!(r.events[0], CombatEvent::Deflected { attacker: Side::Player, defender: Side::Enemy(0) });
+        assert_eq!(e[0].hp, 100);
+    }
+
+    #[test]
+    fn cannot_flee_a_fight_you_started() {
+        let (mut p, mut e) = (player(), [enemy(100, 1, 0, 0)]);
+        let rules = TurnRules { player_initiated: true, ..TurnRules::default() };
+        for seed in 0..20 {
+            let r = resolve_turn(&mut p, &mut e, 0, rules, TurnChoice::Flee, &mut GameRng::for_world(seed));
+            assert_eq!(r.events[0], CombatEvent::MustFinish);
+            assert_ne!(r.outcome, Some(Outcome::Fled));
+        }
+    }
+
+    #[test]
+    fn stunned_enemy_loses_its_swing() {
+        let (mut p, mut e) = (player(), [enemy(100, 50, 0, -100)]);
+        status::apply(&mut e[0].statuses, StatusKind::Stun, 1);
+        let r = resolve_turn(&mut p, &mut e, 0, TurnRules::default(), TurnChoice::UsedItem, &mut GameRng::for_world(4));
+        assert_eq!(r.events, vec![CombatEvent::Stunned { side: Side::Enemy(0) }]);
+        assert!(e[0].statuses.is_empty());
+    }
+
+    #[test]
+    fn poison_ticks_after_the_exchange_and_wears_off() {
+        let (mut p, mut e) = (player(), [enemy(100, 0, 0, -100)]);
+        e[0].on_hit = vec![StatusSpec { kind: StatusKind::Poison, turns: 1, chance: 1.0 }];
+        p.base_defense = -100;
+        let r = resolve_turn(&mut p, &mut e, 0, TurnRules::default(), TurnChoice::UsedItem, &mut GameRng::for_world(5));
+        assert_eq!(&r.events[1..], &[
+            CombatEvent::StatusApplied { target: Side::Player, kind: StatusKind::Poison },
+            CombatEvent::StatusTick { target: Side::Player, kind: StatusKind::Poison, hp: -2 },
+            CombatEvent::StatusEnded { target: Side::Player, kind: StatusKind::Poison },
+        ]);
+        assert_eq!(p.hp, p.max_hp() - 2);
+    }
+
+    #[test]
+    fn skills_act_in_the_attack_slot() {
+        let (mut p, mut e) = (player(), [enemy(100, 5, 0, -100)]);
+        let strike = TurnChoice::Skill(SkillEffect::Strike { power: 2.0 });
+        let r = resolve_turn(&mut p, &mut e, 0, TurnRules::default(), strike, &mut GameRng::for_world(6));
+        assert_eq!(r.events[0], CombatEvent::Hit { attacker: Side::Player, defender: Side::Enemy(0), damage: 24 });
+
+        let guard = TurnChoice::Skill(SkillEffect::Guard { turns: 2 });
+        let r = resolve_turn(&mut p, &mut e, 0, TurnRules::default(), guard, &mut GameRng::for_world(7));
+        assert_eq!(&r.events[..2], &[
+            CombatEvent::StatusApplied { target: Side::Player, kind: StatusKind::Guard },
+            CombatEvent::Deflected { attacker: Side::Enemy(0), defender: Side::Player },
+        ]);
+        assert!(p.statuses.is_empty());
+    }
+
+    #[test]
+    fn ai_moves_replace_the_enemy_attack() {
+        use crate::engine::ai::Behavior;
+
+        let (mut p, mut e) = (player(), [enemy(10, 5, 0, -100)]);
+        e[0].hp = 4;
+        e[0].ai.behavior = Behavior::Healer { amount: 3, below: 0.5, every: 2 };
+        let r = resolve_turn(&mut p, &mut e, 0, TurnRules::default(), TurnChoice::UsedItem, &mut GameRng::for_world(8));
+        assert_eq!(r.events, vec![CombatEvent::Healed { side: Side::Enemy(0), hp: 3 }]);
+        assert_eq!(e[0].hp, 7);
+    }
+
+    #[test]
+    fn groups_act_by_speed_and_fall_together() {
+        let mut p = player();
+        let mut group = [enemy(5, 1, 0, 1), enemy(5, 1, 0, 9), enemy(5, 1, 0, 3)];
+        p.base_speed = 5;
+        assert_eq!(initiative(&p, &group, false), [Side::Enemy(1), Side::Player, Side::Enemy(2), Side::Enemy(0)]);
+        assert_eq!(initiative(&p, &group, true)[3], Side::Player);
+
+        p.base_attack = 50;
+        p.base_defense = 100;
+        let mut rng = GameRng::for_world(9);
+        let r = resolve_turn(&mut p, &mut group, 2, TurnRules::default(), TurnChoice::Fight, &mut rng);
+        assert!(r.events.contains(&CombatEvent::Defeated { enemy: 2 }));
+        assert_eq!(r.outcome, None);
+
+        // The chosen target is down, so the next swing goes to the first one standing.
+        let r = resolve_turn(&mut p, &mut group, 2, TurnRules::default(), TurnChoice::Fight, &mut rng);
+        assert!(r.events.contains(&CombatEvent::Defeated { enemy: 0 }));
+        resolve_turn(&mut p, &mut group, 0, TurnRules::default(), TurnChoice::Fight, &mut rng);
+        assert!(group.iter().all(|e| e.is_down()));
+    }
+}
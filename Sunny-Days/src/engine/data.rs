@@ -0,0 +1,32 @@
+use std::{error::Error, fs, path::Path};
+
+/// One of the JSON files under `assets/data/`.
+pub trait DataFile: Sized {
+    /// What the fallback notice calls it, e.g. "NPC data".
+    const WHAT: &'static str;
+
+    /// Parses the file and checks it.
+    fn parse(json: &str) -> Result<Self, Box<dyn Error>>;
+}
+
+/// Reads `path` (relative to the crate root). If it's missing or broken this
+/// says so on stderr and uses `builtin` instead: the copy of the file shipped
+/// inside the binary. Call it before the terminal goes into raw mode.
+pub fn load_registry<T: DataFile>(path: &str, builtin: &str) -> T {
+    let full = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+    let loaded = fs::read_to_string(&full)
+        .map_err(Box::<dyn Error>::from)
+        .and_then(|json| T::parse(&json));
+    match loaded {
+        Ok(reg) => reg,
+        Err(e) => {
+            eprintln!("{} not loaded ({}: {e}); using the built-in copy", T::WHAT, full.display());
+            parse_builtin(path, builtin)
+        }
+    }
+}
+
+/// Parses the shipped copy of a data file, which the tests keep valid.
+pub fn parse_builtin<T: DataFile>(path: &str, json: &str) -> T {
+    T::parse(json).unwrap_or_else(|e| panic!("built-in {path} is invalid: {e}"))
+}
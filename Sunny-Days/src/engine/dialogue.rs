@@ -0,0 +1,205 @@
+use crate::engine::entity::EquipSlot;
+use crate::engine::item::ItemId;
+use crate::engine::shop::ShopMode;
+use crate::engine::world::GameState;
+
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+
+/// A test against the world, written in data as a short string:
+/// `flag_name`, `has_item:weeping_dagger`, `equipped:sword` (or any other gear
+/// kind: shield, armor, helmet, boots, ring, amulet), each optionally
+/// prefixed with `!` to negate it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Condition {
+    Flag(String),
+    HasItem(ItemId),
+    Equipped(EquipSlot),
+    Not(Box<Condition>),
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        if let Some(rest) = raw.strip_prefix('!') {
+            return Ok(Condition::Not(Box::new(Condition::try_from(rest.to_string())?)));
+        }
+        match raw.split_once(':') {
+            None if raw.is_empty() => Err("empty condition".to_string()),
+            None => Ok(Condition::Flag(raw)),
+            Some(("has_item", id)) => Ok(Condition::HasItem(ItemId::new(id))),
+            Some(("equipped", "sword")) => Ok(Condition::Equipped(EquipSlot::Sword)),
+            Some(("equipped", "shield")) => Ok(Condition::Equipped(EquipSlot::Shield)),
+            Some(("equipped", "armor")) => Ok(Condition::Equipped(EquipSlot::Armor)),
+            Some(("equipped", "helmet")) => Ok(Condition::Equipped(EquipSlot::Helmet)),
+            Some(("equipped", "boots")) => Ok(Condition::Equipped(EquipSlot::Boots)),
+            Some(("equipped", "ring")) => Ok(Condition::Equipped(EquipSlot::Ring)),
+            Some(("equipped", "amulet")) => Ok(Condition::Equipped(EquipSlot::Amulet)),
+            Some((kind, arg)) => Err(format!("unknown condition `{kind}:{arg}`")),
+        }
+    }
+}
+
+impl Condition {
+    /// Flag names this condition reads, for validation.
+    pub fn flags(&self) -> Vec<&str> {
+        match self {
+            Condition::Flag(f) => vec![f.as_str()],
+            Condition::Not(c) => c.flags(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Item id this condition names, for validation.
+    pub fn item(&self) -> Option<&ItemId> {
+        match self {
+            Condition::HasItem(id) => Some(id),
+            Condition::Not(c) => c.item(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    SetFlag(String),
+    ClearFlag(String),
+    /// Put a new copy of an item straight into its slot; whatever was there goes to the backpack.
+    Equip(ItemId),
+    /// Add a new copy of an item to the backpack.
+    GiveItem(ItemId),
+    /// Remove the first item with this id, equipped or in the backpack.
+    TakeItem(ItemId),
+    /// Fight the NPC the player is talking to.
+    StartBattle,
+    SetState(GameState),
+    Log(String),
+    /// Open the merchant's menu once the dialogue closes.
+    OpenShop(ShopMode),
+}
+
+impl Effect {
+    /// Item id this effect names, for validation.
+    pub fn item(&self) -> Option<&ItemId> {
+        match self {
+            Effect::Equip(id) | Effect::GiveItem(id) | Effect::TakeItem(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Choice {
+    pub key: char,
+    #[serde(default)]
+    pub when: Vec<Condition>,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Node {
+    pub pages: Vec<String>,
+    /// Run as soon as the node is shown.
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    /// Offered once the last page is showing.
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+    /// Run when the last page is dismissed without picking a choice.
+    #[serde(default)]
+    pub on_close: Vec<Effect>,
+}
+
+/// Picks the first node whose conditions all hold when the player talks to an NPC.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Branch {
+    #[serde(default)]
+    pub when: Vec<Condition>,
+    pub node: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueGraph {
+    pub start: Vec<Branch>,
+    pub nodes: BTreeMap<String, Node>,
+}
+
+impl DialogueGraph {
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    /// Item ids named by the graph's conditions and effects.
+    pub fn item_refs(&self) -> Vec<&ItemId> {
+        let conds = self.start.iter().flat_map(|b| &b.when)
+            .chain(self.nodes.values().flat_map(|n| n.choices.iter().flat_map(|c| &c.when)))
+            .filter_map(Condition::item);
+        let effects = self.nodes.values()
+            .flat_map(|n| n.effects.iter().chain(&n.on_close).chain(n.choices.iter().flat_map(|c| &c.effects)))
+            .filter_map(Effect::item);
+        conds.chain(effects).collect()
+    }
+
+    /// Every letter the graph's choices are picked with.
+    pub fn choice_keys(&self) -> impl Iterator<Item = char> + '_ {
+        self.nodes.values().flat_map(|n| n.choices.iter().map(|c| c.key))
+    }
+
+    /// Whether any node can start a fight with the NPC.
+    pub fn starts_battle(&self) -> bool {
+        self.nodes.values()
+            .flat_map(|n| n.effects.iter().chain(&n.on_close).chain(n.choices.iter().flat_map(|c| &c.effects)))
+            .any(|e| matches!(e, Effect::StartBattle))
+    }
+
+    /// Checks node references and choice keys; returns flags the graph reads and sets.
+    pub fn validate(&self) -> Result<(Vec<&str>, Vec<&str>), String> {
+        let mut reads = Vec::new();
+        let mut sets = Vec::new();
+
+        if self.start.is_empty() {
+            return Err("dialogue has no start branches".to_string());
+        }
+        for b in &self.start {
+            if !self.nodes.contains_key(&b.node) {
+                return Err(format!("start branch points at missing node `{}`", b.node));
+            }
+            reads.extend(b.when.iter().flat_map(Condition::flags));
+        }
+
+        for (id, node) in &self.nodes {
+            if node.pages.is_empty() {
+                return Err(format!("node `{id}` has no pages"));
+            }
+            let mut effects: Vec<&Effect> = node.effects.iter().chain(&node.on_close).collect();
+            for (i, c) in node.choices.iter().enumerate() {
+                if !c.key.is_ascii_alphabetic() || c.key.is_ascii_lowercase() {
+                    return Err(format!("node `{id}` choice key `{}` must be an uppercase letter", c.key));
+                }
+                if node.choices[..i].iter().any(|o| o.key == c.key) {
+                    return Err(format!("node `{id}` has two choices on key `{}`", c.key));
+                }
+                if let Some(next) = &c.next
+                    && !self.nodes.contains_key(next)
+                {
+                    return Err(format!("node `{id}` choice `{}` points at missing node `{next}`", c.key));
+                }
+                reads.extend(c.when.iter().flat_map(Condition::flags));
+                effects.extend(&c.effects);
+            }
+            for e in effects {
+                if let Effect::SetFlag(f) | Effect::ClearFlag(f) = e {
+                    sets.push(f.as_str());
+                }
+            }
+        }
+        Ok((reads, sets))
+    }
+}
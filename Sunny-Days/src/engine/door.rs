@@ -0,0 +1,73 @@
+use crate::engine::data::{self, DataFile};
+use crate::engine::dialogue::Condition;
+
+use serde::Deserialize;
+
+use std::{error::Error, sync::OnceLock};
+
+const DOOR_DATA: &str = "assets/data/doors.json";
+const BUILTIN_DOORS: &str = include_str!("../../assets/data/doors.json");
+
+/// What it takes to go through the door out of a room.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DoorRule {
+    pub room: usize,
+    /// The door opens once all of these hold.
+    pub requires: Vec<Condition>,
+    /// Logged when the player tries the door too early.
+    pub locked: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DoorRegistry {
+    doors: Vec<DoorRule>,
+}
+
+impl DoorRegistry {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let reg: DoorRegistry = serde_json::from_str(json)?;
+        reg.validate()?;
+        Ok(reg)
+    }
+
+    pub fn builtin() -> Self {
+        data::parse_builtin(DOOR_DATA, BUILTIN_DOORS)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, d) in self.doors.iter().enumerate() {
+            if d.room > 1 {
+                return Err(format!("door rule for room {}, only rooms 0 and 1 exist", d.room));
+            }
+            if self.doors[..i].iter().any(|o| o.room == d.room) {
+                return Err(format!("two door rules for room {}", d.room));
+            }
+        }
+        Ok(())
+    }
+
+    /// The rule for leaving `room`; None means its door is always open.
+    pub fn rule(&self, room: usize) -> Option<&DoorRule> {
+        self.doors.iter().find(|d| d.room == room)
+    }
+
+    /// Flag names the rules read, so NPC validation can check something sets them.
+    pub fn flags(&self) -> Vec<&str> {
+        self.doors.iter().flat_map(|d| d.requires.iter().flat_map(Condition::flags)).collect()
+    }
+}
+
+impl DataFile for DoorRegistry {
+    const WHAT: &'static str = "Door data";
+
+    fn parse(json: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_json(json)
+    }
+}
+
+static REGISTRY: OnceLock<DoorRegistry> = OnceLock::new();
+
+/// Door requirements, read from `assets/data/doors.json` the first time they're needed.
+pub fn registry() -> &'static DoorRegistry {
+    REGISTRY.get_or_init(|| data::load_registry(DOOR_DATA, BUILTIN_DOORS))
+}
@@ -1,63 +1,219 @@
+use crate::engine::item::{self, ItemId};
+use crate::engine::rarity::Rarity;
+use crate::engine::stats::{Modifier, Stat, Stats};
+use crate::engine::status::{self, StatusEffect, StatusSpec};
+use crate::engine::xp::{Experience, StatChoice};
 use crate::map::Map;
-use std::time::{Duration, Instant};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use std::collections::BTreeMap;
+
+/// What kind of gear an item is, which decides where it can be worn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EquipSlot {
     Sword,
     Shield,
+    Armor,
+    Helmet,
+    Boots,
+    Ring,
+    Amulet,
+    Bag,
 }
 
-#[derive(Debug, Clone)]
+impl EquipSlot {
+    /// Places on the body this kind of item fits, in the order they're filled.
+    pub fn gear_slots(self) -> &'static [GearSlot] {
+        match self {
+            EquipSlot::Sword => &[GearSlot::MainHand],
+            EquipSlot::Shield => &[GearSlot::OffHand],
+            EquipSlot::Armor => &[GearSlot::Armor],
+            EquipSlot::Helmet => &[GearSlot::Helmet],
+            EquipSlot::Boots => &[GearSlot::Boots],
+            EquipSlot::Ring => &[GearSlot::Ring1, GearSlot::Ring2],
+            EquipSlot::Amulet => &[GearSlot::Amulet],
+            EquipSlot::Bag => &[GearSlot::Bag],
+        }
+    }
+
+    /// "shield", as in "Still missing: shield."
+    pub fn noun(self) -> &'static str {
+        match self {
+            EquipSlot::Sword => "sword",
+            EquipSlot::Shield => "shield",
+            EquipSlot::Armor => "armor",
+            EquipSlot::Helmet => "helmet",
+            EquipSlot::Boots => "boots",
+            EquipSlot::Ring => "ring",
+            EquipSlot::Amulet => "amulet",
+            EquipSlot::Bag => "bag",
+        }
+    }
+}
+
+/// How many hands a weapon takes. Only means anything on `Sword` items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grip {
+    #[default]
+    OneHanded,
+    TwoHanded, // fills both hands, so nothing can go in the off hand
+    OffHand,   // light enough for either hand; a second one is dual wielded
+}
+
+/// A place on the paper doll, head to toe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GearSlot {
+    Helmet,
+    Amulet,
+    Armor,
+    MainHand,
+    OffHand,
+    Ring1,
+    Ring2,
+    Boots,
+    Bag,
+}
+
+impl GearSlot {
+    pub const ALL: [GearSlot; 9] = [
+        GearSlot::Helmet, GearSlot::Amulet, GearSlot::Armor, GearSlot::MainHand,
+        GearSlot::OffHand, GearSlot::Ring1, GearSlot::Ring2, GearSlot::Boots, GearSlot::Bag,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GearSlot::Helmet => "Helmet",
+            GearSlot::Amulet => "Amulet",
+            GearSlot::Armor => "Armor",
+            GearSlot::MainHand => "Main hand",
+            GearSlot::OffHand => "Off hand",
+            GearSlot::Ring1 => "Ring 1",
+            GearSlot::Ring2 => "Ring 2",
+            GearSlot::Boots => "Boots",
+            GearSlot::Bag => "Bag",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Equipment {
+    pub id: ItemId, // catalogue template it was made from
     pub name: String,
     pub slot: EquipSlot,
     pub hp_bonus: i32, // NEW: Equipment can now modify Max HP
     pub atk_bonus: i32,
     pub def_bonus: i32,
     pub speed_bonus: i32,
+    #[serde(default)]
+    pub on_hit: Vec<StatusSpec>, // inflicted on whoever this weapon hits
+    #[serde(default)]
+    pub skills: Vec<String>, // skill ids usable while it's equipped
+    #[serde(default)]
+    pub rarity: Rarity,
+    #[serde(default)]
+    pub grip: Grip,
+    #[serde(default)]
+    pub mods: Vec<Modifier>, // on top of the flat bonuses, e.g. a percentage of max HP
+    #[serde(default)]
+    pub bag_slots: usize, // extra consumable slots while worn
 }
 
-#[derive(Debug, Clone)]
+impl Equipment {
+    pub fn modifiers(&self) -> impl Iterator<Item = Modifier> + '_ {
+        [
+            Modifier::Add(Stat::MaxHp, self.hp_bonus),
+            Modifier::Add(Stat::Attack, self.atk_bonus),
+            Modifier::Add(Stat::Defense, self.def_bonus),
+            Modifier::Add(Stat::Speed, self.speed_bonus),
+        ]
+        .into_iter()
+        .chain(self.mods.iter().copied())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Consumable {
+    pub id: ItemId,
     pub name: String,
     pub heal: i32,
     pub atk_bonus: i32,
     pub def_bonus: i32,
+    #[serde(default)]
+    pub statuses: Vec<StatusSpec>, // applied to whoever uses it
+    #[serde(default = "single")]
+    pub max_stack: u32,
 }
 
-#[derive(Debug, Clone)]
+fn single() -> u32 {
+    1
+}
+
+/// Copies of one consumable sharing an inventory slot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stack {
+    pub item: Consumable,
+    #[serde(deserialize_with = "at_least_one")]
+    pub count: u32,
+}
+
+/// Saves are hand-editable, and an empty stack would underflow on use.
+fn at_least_one<'de, D: Deserializer<'de>>(d: D) -> Result<u32, D::Error> {
+    match u32::deserialize(d)? {
+        0 => Err(D::Error::custom("a stack holds at least one item")),
+        n => Ok(n),
+    }
+}
+
+impl Stack {
+    /// "Sunny Jerky x3", or just the name for a single one.
+    pub fn label(&self) -> String {
+        if self.count > 1 { format!("{} x{}", self.item.name, self.count) } else { self.item.name.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TempBuff {
     pub atk_bonus: i32,
     pub def_bonus: i32,
     pub speed_bonus: i32,
-    pub expires_at: Instant,
+    pub turns: u32, // steps on the map or battle turns left
+}
+
+impl TempBuff {
+    pub fn modifiers(&self) -> [Modifier; 3] {
+        [
+            Modifier::Add(Stat::Attack, self.atk_bonus),
+            Modifier::Add(Stat::Defense, self.def_bonus),
+            Modifier::Add(Stat::Speed, self.speed_bonus),
+        ]
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum InvTab {
-    Weapons,
+    Equipment,
     Consumables,
     Backpack,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Inventory {
-    pub sword: Option<Equipment>,
-    pub shield: Option<Equipment>,
+    pub equipped: BTreeMap<GearSlot, Equipment>,
 
-    pub consumables: Vec<Consumable>, 
+    pub consumables: Vec<Stack>,
     pub backpack: Vec<Equipment>,
 
     pub tab: InvTab,
-    pub weapon_cursor: usize,
+    pub gear_cursor: usize, // into `GearSlot::ALL`
     pub consumable_cursor: usize,
     pub backpack_cursor: usize,
 }
 
 #[derive(Debug, Clone)]
 pub enum InvSelection {
-    SwordSlot,
-    ShieldSlot,
+    Gear(GearSlot),
     Consumable(usize),
     BackpackItem(usize),
     None,
@@ -66,12 +222,11 @@ pub enum InvSelection {
 impl Inventory {
     pub fn default_loadout() -> Self {
         Self {
-            sword: None,
-            shield: None,
+            equipped: BTreeMap::new(),
             consumables: Vec::new(),
             backpack: Vec::new(),
-            tab: InvTab::Weapons,
-            weapon_cursor: 0,
+            tab: InvTab::Equipment,
+            gear_cursor: 0,
             consumable_cursor: 0,
             backpack_cursor: 0,
         }
@@ -79,13 +234,13 @@ impl Inventory {
 
     pub fn toggle_tab(&mut self) {
         self.tab = match self.tab {
-            InvTab::Weapons => InvTab::Consumables,
+            InvTab::Equipment => InvTab::Consumables,
             InvTab::Consumables => InvTab::Backpack,
-            InvTab::Backpack => InvTab::Weapons,
+            InvTab::Backpack => InvTab::Equipment,
         };
 
-        if self.weapon_cursor > 1 {
-            self.weapon_cursor = 1;
+        if self.gear_cursor >= GearSlot::ALL.len() {
+            self.gear_cursor = GearSlot::ALL.len() - 1;
         }
         if !self.consumables.is_empty() && self.consumable_cursor >= self.consumables.len() {
             self.consumable_cursor = self.consumables.len() - 1;
@@ -97,15 +252,15 @@ impl Inventory {
 
     pub fn move_cursor(&mut self, delta: i32) {
         match self.tab {
-            InvTab::Weapons => {
-                let len = 2;
-                let mut idx = self.weapon_cursor as i32 + delta;
+            InvTab::Equipment => {
+                let len = GearSlot::ALL.len();
+                let mut idx = self.gear_cursor as i32 + delta;
                 if idx < 0 {
                     idx = len as i32 - 1;
                 } else if idx >= len as i32 {
                     idx = 0;
                 }
-                self.weapon_cursor = idx as usize;
+                self.gear_cursor = idx as usize;
             }
 
             InvTab::Consumables => {
@@ -142,13 +297,7 @@ impl Inventory {
 
     pub fn selection(&self) -> InvSelection {
         match self.tab {
-            InvTab::Weapons => {
-                if self.weapon_cursor == 0 {
-                    InvSelection::SwordSlot
-                } else {
-                    InvSelection::ShieldSlot
-                }
-            }
+            InvTab::Equipment => InvSelection::Gear(GearSlot::ALL[self.gear_cursor.min(GearSlot::ALL.len() - 1)]),
             InvTab::Consumables => {
                 if self.consumables.is_empty() {
                     InvSelection::None
@@ -166,6 +315,7 @@ impl Inventory {
         }
     }
 
+    /// Takes one item off the selected stack.
     pub fn take_selected_consumable(&mut self) -> Option<Consumable> {
         if self.tab != InvTab::Consumables {
             return None;
@@ -174,17 +324,118 @@ impl Inventory {
             return None;
         }
         let idx = self.consumable_cursor.min(self.consumables.len() - 1);
-        Some(self.consumables.remove(idx))
+        Some(self.take_consumable(idx))
+    }
+
+    /// Takes one item off stack `idx`, dropping the stack once it's empty.
+    pub fn take_consumable(&mut self, idx: usize) -> Consumable {
+        let stack = &mut self.consumables[idx];
+        stack.count -= 1;
+        let item = stack.item.clone();
+        if stack.count == 0 {
+            self.consumables.remove(idx);
+            self.consumable_cursor = self.consumable_cursor.min(self.consumables.len().saturating_sub(1));
+        }
+        item
+    }
+
+    /// Consumable slots: the catalogue's base amount plus whatever worn bags add.
+    pub fn capacity(&self) -> usize {
+        item::registry().consumable_slots() + self.worn().map(|e| e.bag_slots).sum::<usize>()
+    }
+
+    /// Whether `c` would fit, on a stack with room or in a free slot.
+    pub fn can_hold(&self, c: &Consumable) -> bool {
+        self.consumables.iter().any(|s| s.item.id == c.id && s.count < c.max_stack)
+            || self.consumables.len() < self.capacity()
+    }
+
+    /// Puts `c` on its first stack with room, or starts a new one. False if
+    /// there's no room for it.
+    pub fn add_consumable(&mut self, c: Consumable) -> bool {
+        if let Some(stack) = self.consumables.iter_mut().find(|s| s.item.id == c.id && s.count < c.max_stack) {
+            stack.count += 1;
+        } else if self.consumables.len() < self.capacity() {
+            self.consumables.push(Stack { item: c, count: 1 });
+        } else {
+            return false;
+        }
+        true
+    }
+
+    pub fn gear(&self, slot: GearSlot) -> Option<&Equipment> {
+        self.equipped.get(&slot)
+    }
+
+    /// Everything currently worn, head to toe.
+    pub fn worn(&self) -> impl Iterator<Item = &Equipment> {
+        self.equipped.values()
+    }
+
+    /// Whether an item of this kind is worn anywhere. A two-handed weapon
+    /// counts as the shield too, since it's what fills the off hand.
+    pub fn wearing(&self, kind: EquipSlot) -> bool {
+        self.worn().any(|e| e.slot == kind) || (kind == EquipSlot::Shield && self.two_handed())
+    }
+
+    /// Whether the main hand holds a two-handed weapon.
+    pub fn two_handed(&self) -> bool {
+        self.gear(GearSlot::MainHand).is_some_and(|e| e.grip == Grip::TwoHanded)
+    }
+
+    /// Whether a weapon is held in the off hand, which earns a second, weaker swing.
+    pub fn dual_wielding(&self) -> bool {
+        self.gear(GearSlot::OffHand).is_some_and(|e| e.slot == EquipSlot::Sword)
+    }
+
+    /// Where `eq` would go: the first free place it fits, otherwise the first
+    /// place (swapping out what's there). Off-hand weapons fit either hand.
+    fn slot_for(&self, eq: &Equipment) -> GearSlot {
+        let slots = match (eq.slot, eq.grip) {
+            (EquipSlot::Sword, Grip::OffHand) if !self.two_handed() => &[GearSlot::MainHand, GearSlot::OffHand],
+            (kind, _) => kind.gear_slots(),
+        };
+        slots.iter().copied().find(|s| !self.equipped.contains_key(s)).unwrap_or(slots[0])
+    }
+
+    /// Where `eq` would go, and every place it would empty on the way: its
+    /// own, plus the other hand when a two-hander is involved.
+    fn displaced_by(&self, eq: &Equipment) -> (GearSlot, Vec<GearSlot>) {
+        let slot = self.slot_for(eq);
+        let mut clear = vec![slot];
+        if eq.grip == Grip::TwoHanded {
+            clear.push(GearSlot::OffHand);
+        } else if slot == GearSlot::OffHand && self.two_handed() {
+            clear.push(GearSlot::MainHand);
+        }
+        (slot, clear)
+    }
+
+    /// The bag that has to be emptied first if the gear in `taking_off` came
+    /// off and `putting_on` went on, because the consumables wouldn't fit.
+    pub fn bag_in_the_way(&self, taking_off: &[GearSlot], putting_on: Option<&Equipment>) -> Option<&Equipment> {
+        let bags = || taking_off.iter().filter_map(|&s| self.gear(s)).filter(|e| e.bag_slots > 0);
+        let lost: usize = bags().map(|e| e.bag_slots).sum();
+        let gained = putting_on.map_or(0, |e| e.bag_slots);
+        if self.consumables.len() + lost > self.capacity() + gained { bags().next() } else { None }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Gear `Player::equip` handed back because it would take off a bag whose
+/// contents have nowhere else to go.
+#[derive(Debug)]
+pub struct BagInTheWay {
+    pub item: Box<Equipment>,
+    pub bag: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
     pub x: i32,
     pub y: i32,
 
-    pub hp: i32,
-    pub max_hp: i32,
+    pub hp: i32, // kept within `max_hp()`
+    pub base_max_hp: i32,
 
     pub base_attack: i32,
     pub base_defense: i32,
@@ -192,6 +443,9 @@ pub struct Player {
 
     pub inventory: Inventory,
     pub buffs: Vec<TempBuff>,
+    pub statuses: Vec<StatusEffect>, // only tick during battle turns
+    pub exp: Experience,
+    pub gold: u32,
 }
 
 impl Player {
@@ -201,16 +455,38 @@ impl Player {
             x,
             y,
             hp: max_hp,
-            max_hp,
+            base_max_hp: max_hp,
             base_attack: 10,
             base_defense: 8,
             base_speed: 5,
             inventory: Inventory::default_loadout(),
             buffs: Vec::new(),
+            statuses: Vec::new(),
+            exp: Experience::default(),
+            gold: 0,
+        }
+    }
+
+    /// Puts one unspent stat point into `choice`; false if there are none left.
+    pub fn spend_point(&mut self, choice: StatChoice) -> bool {
+        if self.exp.points == 0 {
+            return false;
+        }
+        self.exp.points -= 1;
+        let amount = choice.per_point();
+        match choice {
+            StatChoice::Attack => self.base_attack += amount,
+            StatChoice::Defense => self.base_defense += amount,
+            StatChoice::Speed => self.base_speed += amount,
+            StatChoice::MaxHp => {
+                self.base_max_hp += amount;
+                self.hp += amount;
+            }
         }
+        true
     }
 
-    pub fn add_temp_buff(&mut self, atk: i32, def: i32, speed: i32, duration: Duration) {
+    pub fn add_temp_buff(&mut self, atk: i32, def: i32, speed: i32, turns: u32) {
         if atk == 0 && def == 0 && speed == 0 {
             return;
         }
@@ -218,76 +494,104 @@ impl Player {
             atk_bonus: atk,
             def_bonus: def,
             speed_bonus: speed,
-            expires_at: Instant::now() + duration,
+            turns,
         });
     }
 
-    pub fn purge_expired_buffs(&mut self) {
-        let now = Instant::now();
-        self.buffs.retain(|b| b.expires_at > now);
+    /// Counts every buff down by one turn; true if any ran out.
+    pub fn tick_buffs(&mut self) -> bool {
+        let before = self.buffs.len();
+        self.buffs.retain_mut(|b| {
+            b.turns = b.turns.saturating_sub(1);
+            b.turns > 0
+        });
+        self.clamp_hp();
+        self.buffs.len() < before
     }
 
-    fn active_buff_sums(&self) -> (i32, i32, i32) {
-        let now = Instant::now();
-        let mut atk = 0;
-        let mut def = 0;
-        let mut spd = 0;
+    /// Effective stats: the base ones run through everything worn, every
+    /// buff and every status.
+    pub fn stats(&self) -> Stats {
+        let base = Stats {
+            max_hp: self.base_max_hp,
+            attack: self.base_attack,
+            defense: self.base_defense,
+            speed: self.base_speed,
+        };
+        let mods = self.inventory.worn().flat_map(Equipment::modifiers)
+            .chain(self.buffs.iter().flat_map(TempBuff::modifiers))
+            .chain(status::modifiers(&self.statuses));
+        base.apply(mods)
+    }
 
-        for b in &self.buffs {
-            if b.expires_at > now {
-                atk += b.atk_bonus;
-                def += b.def_bonus;
-                spd += b.speed_bonus;
-            }
-        }
-        (atk, def, spd)
+    pub fn max_hp(&self) -> i32 {
+        self.stats().max_hp
     }
 
     pub fn attack(&self) -> i32 {
-        let mut v = self.base_attack;
-        if let Some(sw) = &self.inventory.sword {
-            v += sw.atk_bonus;
-        }
-        if let Some(sh) = &self.inventory.shield {
-            v += sh.atk_bonus;
-        }
-        let (atk_b, _, _) = self.active_buff_sums();
-        v += atk_b;
-        v
+        self.stats().attack
     }
 
     pub fn defense(&self) -> i32 {
-        let mut v = self.base_defense;
-        if let Some(sw) = &self.inventory.sword {
-            v += sw.def_bonus;
-        }
-        if let Some(sh) = &self.inventory.shield {
-            v += sh.def_bonus;
-        }
-        let (_, def_b, _) = self.active_buff_sums();
-        v += def_b;
-        v
+        self.stats().defense
     }
 
     pub fn speed(&self) -> i32 {
-        let mut v = self.base_speed;
-        if let Some(sw) = &self.inventory.sword {
-            v += sw.speed_bonus;
-        }
-        if let Some(sh) = &self.inventory.shield {
-            v += sh.speed_bonus;
+        self.stats().speed
+    }
+
+    /// What wearing `eq` would leave the stats at, and the names of what it
+    /// would take off to make room.
+    pub fn preview_equip(&self, eq: &Equipment) -> (Stats, Vec<String>) {
+        let mut p = self.clone();
+        match p.equip(eq.clone()) {
+            Ok((_, removed)) => (p.stats(), removed),
+            Err(_) => (self.stats(), Vec::new()),
         }
-        let (_, _, spd_b) = self.active_buff_sums();
-        v += spd_b;
-        v
     }
 
-    pub fn equip_sword(&mut self, eq: Equipment) {
-        self.inventory.sword = Some(eq);
+    /// HP after using `c`.
+    pub fn hp_after(&self, c: &Consumable) -> i32 {
+        (self.hp + c.heal).min(self.max_hp())
+    }
+
+    /// Heals up to max HP; a negative amount hurts.
+    pub fn heal(&mut self, amount: i32) {
+        self.hp = (self.hp + amount).min(self.max_hp());
+    }
+
+    /// Pulls HP back under a max that just went down.
+    fn clamp_hp(&mut self) {
+        self.hp = self.hp.min(self.max_hp());
+    }
+
+    /// Puts on a piece of gear, moving whatever it replaces to the backpack.
+    /// A two-handed weapon also clears the off hand, and anything held in the
+    /// off hand clears a two-handed weapon. Returns where it went and the
+    /// names of what came off. Refuses, handing `eq` back, when that would
+    /// take off a bag the consumables don't fit without.
+    pub fn equip(&mut self, eq: Equipment) -> Result<(GearSlot, Vec<String>), BagInTheWay> {
+        let (slot, clear) = self.inventory.displaced_by(&eq);
+        if let Some(bag) = self.inventory.bag_in_the_way(&clear, Some(&eq)) {
+            return Err(BagInTheWay { bag: bag.name.clone(), item: Box::new(eq) });
+        }
+        let mut removed = Vec::new();
+        for s in clear {
+            if let Some(old) = self.unequip(s) {
+                removed.push(old.name.clone());
+                self.inventory.backpack.push(old);
+            }
+        }
+        self.inventory.equipped.insert(slot, eq);
+        self.clamp_hp();
+        Ok((slot, removed))
     }
 
-    pub fn equip_shield(&mut self, eq: Equipment) {
-        self.inventory.shield = Some(eq);
+    /// Takes off whatever is in `slot`.
+    pub fn unequip(&mut self, slot: GearSlot) -> Option<Equipment> {
+        let eq = self.inventory.equipped.remove(&slot)?;
+        self.clamp_hp();
+        Some(eq)
     }
 
     pub fn try_move(&mut self, dx: i32, dy: i32, map: &Map) {
@@ -1,7 +1,10 @@
 use crate::audio::Music;
 use crate::engine::action::Action;
-use crate::engine::world::{World, GameState};
-use crate::tui::{input::is_press, renderer::render};
+use crate::engine::{door, item, npc, quest, skill};
+use crate::engine::replay::{Divergence, Playback, Recorder, Replay};
+use crate::engine::save::{SaveSlot, SlotManager, SLOT_COUNT};
+use crate::engine::world::{World, GameState, MAP_HEIGHT, MAP_WIDTH};
+use crate::tui::{input::is_press, keymap::{Command, Context, KeyMap}, renderer::render};
 
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
@@ -13,12 +16,35 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 
 use std::{
     io,
+    path::PathBuf,
     time::{Duration, Instant},
 };
 
 const MOVE_COOLDOWN_MS: u64 = 90;
 
-pub fn run() -> std::io::Result<()> {
+type Term = Terminal<CrosstermBackend<io::Stdout>>;
+
+/// Command-line options (see `main.rs`).
+#[derive(Debug)]
+pub struct RunOptions {
+    pub replay: Option<PathBuf>, // play this recording instead of a live game
+    pub record: Option<PathBuf>, // where to write this session; defaults to replays/last.json
+    pub speed: f64,              // playback speed; 0 plays as fast as possible
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self { replay: None, record: None, speed: 1.0 }
+    }
+}
+
+pub fn run(opts: RunOptions) -> std::io::Result<()> {
+    // Read the replay before the screen is taken over so errors are visible.
+    let replay = match &opts.replay {
+        Some(path) => Some(Replay::load(path).map_err(|e| io::Error::other(format!("{}: {e}", path.display())))?),
+        None => None,
+    };
+
     let _music = match Music::start_loop("assets/Background1.mp3") {
         Ok(m) => Some(m),
         Err(e) => {
@@ -27,6 +53,18 @@ pub fn run() -> std::io::Result<()> {
         }
     };
 
+    // Load every data file and the key bindings up front so any warning prints
+    // before the screen is taken over.
+    npc::registry();
+    quest::registry();
+    item::registry();
+    skill::registry();
+    door::registry();
+    let (keys, warning) = KeyMap::load();
+    if let Some(w) = warning {
+        eprintln!("{w}");
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -34,8 +72,49 @@ pub fn run() -> std::io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
+    let outcome = match replay {
+        Some(replay) => play_back(&mut terminal, &keys, replay, opts.speed).map(|(world, result)| (world, None, Some(result))),
+        None => play(&mut terminal, &keys).map(|(world, recording)| (world, recording, None)),
+    };
+
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    let (world, recording, replayed) = outcome?;
+    if world.player.hp <= 0 {
+        println!("You died.");
+    }
+    if let Some(recording) = recording {
+        let path = opts.record.unwrap_or_else(Replay::default_path);
+        if let Err(e) = recording.save(&path) {
+            eprintln!("Recording not saved: {e}");
+        }
+    }
+    match replayed {
+        Some(Ok(count)) => println!("Replay matched the recording ({count} actions)."),
+        Some(Err(d)) => println!("Replay {d}"),
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// A live game. Returns the final world and the session's recording, unless
+/// a save was loaded part way (recordings always start from a new game).
+fn play(terminal: &mut Term, keys: &KeyMap) -> io::Result<(World, Option<Replay>)> {
     let seed = rand::random::<u64>();
-    let mut world = World::new(seed, 80, 45);
+    let mut world = World::new(seed, MAP_WIDTH, MAP_HEIGHT);
+    let mut recorder = Some(Recorder::new(&world));
+
+    let slots = SlotManager::default_dir();
+    let mut slot_list = slots.list();
+    // Ctrl+S writes here; loading a manual slot makes it the active one
+    let mut active_slot = SaveSlot::Manual(1);
 
     let tick_rate = Duration::from_millis(60);
     let mut last_move_time = Instant::now() - Duration::from_millis(MOVE_COOLDOWN_MS);
@@ -48,11 +127,11 @@ pub fn run() -> std::io::Result<()> {
         // Check Death
         if world.player.hp <= 0 {
             terminal.clear()?;
-            println!("You died. Press Ctrl+C to quit.");
+            println!("You died. Press {} to quit.", keys.keys_label(Context::Global, Command::Quit));
             break;
         }
 
-        if let Err(_) = terminal.draw(|f| render(f, &world)) {
+        if let Err(_) = terminal.draw(|f| render(f, &world, &slot_list, keys)) {
             terminal.autoresize()?;
             terminal.clear()?;
             continue;
@@ -70,91 +149,65 @@ pub fn run() -> std::io::Result<()> {
                         continue;
                     }
 
-                    // Quit with Ctrl+C anywhere
-                    if key.modifiers.contains(KeyModifiers::CONTROL) {
-                        if let KeyCode::Char('c') | KeyCode::Char('q') = key.code {
-                            running = world.apply_action(Action::Quit);
-                            continue;
-                        }
+                    // Quit and save chords (Ctrl+C, Ctrl+S, F1-F3 by default) work anywhere
+                    let global = keys.lookup(Context::Global, &key);
+                    if global == Some(Command::Quit) {
+                        running = world.apply_action(Action::Quit);
+                        if let Some(rec) = &mut recorder { rec.record(Action::Quit, &world); }
+                        continue;
+                    }
+                    if let Some(slot) = global.and_then(|cmd| cmd.save_slot(active_slot)) {
+                        active_slot = slot;
+                        save_to_slot(&slots, active_slot, &mut world);
+                        slot_list = slots.list();
+                        continue;
                     }
 
-                    let mut action = match world.state {
-                        GameState::Title | GameState::Intro => match key.code {
-                            KeyCode::Char(' ') | KeyCode::Enter | KeyCode::Char('e') | KeyCode::Char('E') => Action::Confirm,
-                            _ => Action::None,
-                        },
-
-                        GameState::Dialogue => match key.code {
-                            KeyCode::Char(' ') | KeyCode::Enter | KeyCode::Char('e') | KeyCode::Char('E') => Action::Confirm,
-                            KeyCode::Char(c) if c.is_ascii_alphabetic() => Action::Choice(c),
-                            _ => Action::None,
-                        },
-                        
-                        GameState::Fin => match key.code {
-                            // Wait for quit via Ctrl+C (handled above) or maybe 'q'
-                            KeyCode::Char('q') | KeyCode::Char('Q') => Action::Quit,
-                            _ => Action::None,
-                        },
-
-                        GameState::Battle => {
-                            if world.inventory_open {
-                                match key.code {
-                                    KeyCode::Char('i') | KeyCode::Esc => Action::ToggleInventory,
-                                    KeyCode::Up => Action::InventoryUp,
-                                    KeyCode::Down => Action::InventoryDown,
-                                    KeyCode::Char(' ') => Action::UseConsumable,
-                                    _ => Action::None,
-                                }
-                            } else {
-                                let now = Instant::now();
-                                let elapsed = now.duration_since(last_battle_input);
-                                let penalty = elapsed.as_secs() >= 10;
-                                
-                                let act = match key.code {
-                                    KeyCode::Char('1') => Action::BattleOption(1, penalty),
-                                    KeyCode::Char('2') => Action::BattleOption(2, penalty),
-                                    KeyCode::Char('3') => Action::BattleOption(3, penalty),
-                                    _ => Action::None,
-                                };
-                                
-                                if !matches!(act, Action::None) {
-                                    last_battle_input = now;
-                                }
-                                act
+                    // Title screen: 1-3 continue a slot, A continues the autosave
+                    if world.state == GameState::Title {
+                        let picked = match key.code {
+                            KeyCode::Char(c @ '1'..='9') => {
+                                let n = c as usize - '0' as usize;
+                                (n <= SLOT_COUNT).then_some(SaveSlot::Manual(n))
                             }
-                        },
-
-                        GameState::Playing => {
-                            if world.stats_open {
-                                match key.code {
-                                    KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => Action::ToggleStats,
-                                    _ => Action::None,
-                                }
-                            } else if world.inventory_open {
-                                match key.code {
-                                    KeyCode::Char('t') | KeyCode::Char('T') => Action::ToggleInvTab, // NEW
-                                    KeyCode::Char('q') | KeyCode::Char('Q') => Action::ToggleStats,
-                                    KeyCode::Char('i') | KeyCode::Char('I') | KeyCode::Esc => Action::ToggleInventory,
-                                    KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('W') => Action::InventoryUp,
-                                    KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('S') => Action::InventoryDown,
-                                    KeyCode::Char(' ') => Action::UseConsumable,
-                                    _ => Action::None,
-                                }
-                            } else {
-                                match key.code {
-                                    KeyCode::Char('q') | KeyCode::Char('Q') => Action::ToggleStats,
-                                    KeyCode::Char('i') | KeyCode::Char('I') => Action::ToggleInventory,
-                                    KeyCode::Char('e') | KeyCode::Char('E') => Action::Interact,
-
-                                    KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('W') => Action::Move(0, -1),
-                                    KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('S') => Action::Move(0, 1),
-                                    KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('A') => Action::Move(-1, 0),
-                                    KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('D') => Action::Move(1, 0),
-
-                                    _ => Action::None,
+                            KeyCode::Char('a') | KeyCode::Char('A') => Some(SaveSlot::Autosave),
+                            _ => None,
+                        };
+                        if let Some(slot) = picked {
+                            if slot_list.iter().any(|s| s.slot == slot && s.meta.is_some()) {
+                                match slots.load(slot) {
+                                    Ok(loaded) => {
+                                        world = loaded;
+                                        world.push_log(format!("Loaded {}.", slot.label()));
+                                        if let SaveSlot::Manual(_) = slot {
+                                            active_slot = slot;
+                                        }
+                                        last_battle_input = Instant::now();
+                                        if recorder.take().is_some() {
+                                            world.push_log("Recording stopped (replays start from a new game).");
+                                        }
+                                    }
+                                    Err(e) => world.push_log(format!("Load failed: {e}")),
                                 }
                             }
+                            continue;
                         }
+                    }
+
+                    let ctx = Context::of(&world);
+                    let mut action = match keys.lookup(ctx, &key) {
+                        Some(cmd) if cmd.is_battle_option() => {
+                            let now = Instant::now();
+                            let penalty = now.duration_since(last_battle_input).as_secs() >= 10;
+                            last_battle_input = now;
+                            cmd.action(penalty)
+                        }
+                        Some(cmd) => cmd.action(false),
+                        // Unbound letters pick dialogue choices
+                        None => match key.code {
+                            KeyCode::Char(c) if ctx == Context::Dialogue && c.is_ascii_alphabetic() => Action::Choice(c),
+                            _ => Action::None,
+                        },
                     };
 
                     if let Action::Move(_, _) = action {
@@ -169,6 +222,14 @@ pub fn run() -> std::io::Result<()> {
                     // If transitioning INTO Battle, reset timer
                     let old_state = world.state.clone();
                     running = world.apply_action(action);
+                    if let Some(rec) = &mut recorder { rec.record(action, &world); }
+                    if world.autosave_requested {
+                        world.autosave_requested = false;
+                        if let Err(e) = slots.save(SaveSlot::Autosave, &world) {
+                            world.push_log(format!("Autosave failed: {e}"));
+                        }
+                        slot_list = slots.list();
+                    }
                     if old_state != GameState::Battle && world.state == GameState::Battle {
                         last_battle_input = Instant::now();
                     }
@@ -178,20 +239,80 @@ pub fn run() -> std::io::Result<()> {
             }
         } else {
             running = world.apply_action(Action::None);
+            if let Some(rec) = &mut recorder { rec.record(Action::None, &world); }
         }
     }
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-    
-    if world.player.hp <= 0 {
-        println!("You died.");
+    Ok((world, recorder.map(Recorder::finish)))
+}
+
+/// Plays a recording through the normal renderer. Space pauses, +/- change
+/// speed, q or Esc stops. Playback halts at the first divergence so the
+/// screen shows where it happened.
+fn play_back(terminal: &mut Term, keys: &KeyMap, replay: Replay, speed: f64) -> io::Result<(World, Result<usize, Box<Divergence>>)> {
+    let mut world = replay.fresh_world();
+    let total = replay.entries.len();
+    let mut playback = Playback::new(replay);
+
+    let tick_rate = Duration::from_millis(15);
+    let mut speed = speed.max(0.0);
+    let mut paused = false;
+    let mut clock_ms = 0.0; // position in the recording's own time
+    let mut last_frame = Instant::now();
+    let mut result: Option<Result<usize, Box<Divergence>>> = None;
+
+    loop {
+        if terminal.draw(|f| render(f, &world, &[], keys)).is_err() {
+            terminal.autoresize()?;
+            terminal.clear()?;
+            continue;
+        }
+
+        let now = Instant::now();
+        if !paused { clock_ms += now.duration_since(last_frame).as_secs_f64() * 1000.0 * speed; }
+        last_frame = now;
+
+        while result.is_none() && !paused {
+            let Some(ms) = playback.next_ms() else {
+                result = Some(Ok(total));
+                world.push_log("Replay finished. Press q to exit.");
+                break;
+            };
+            if speed > 0.0 && ms as f64 > clock_ms { break; }
+            if let Err(d) = playback.step(&mut world) {
+                world.push_log(format!("Replay {d}"));
+                world.push_log("Press q to exit.");
+                result = Some(Err(d));
+            }
+        }
+
+        if event::poll(tick_rate)?
+            && let Event::Key(key) = event::read()?
+            && is_press(&key)
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => break,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                KeyCode::Char(' ') => paused = !paused,
+                KeyCode::Char('+') | KeyCode::Char('=') if speed > 0.0 => speed = (speed * 2.0).min(64.0),
+                KeyCode::Char('-') if speed > 0.0 => speed = (speed / 2.0).max(0.125),
+                _ => {}
+            }
+        }
     }
 
-    Ok(())
+    // Stopped early: report what was checked so far.
+    let result = result.unwrap_or(Ok(total - playback.remaining()));
+    Ok((world, result))
+}
+
+fn save_to_slot(slots: &SlotManager, slot: SaveSlot, world: &mut World) {
+    // Nothing worth keeping before the run has started or after the credits.
+    if matches!(world.state, GameState::Title | GameState::Fin) {
+        return;
+    }
+    match slots.save(slot, world) {
+        Ok(()) => world.push_log(format!("Saved to {}.", slot.label())),
+        Err(e) => world.push_log(format!("Save failed: {e}")),
+    }
 }
\ No newline at end of file
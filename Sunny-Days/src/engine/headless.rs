@@ -0,0 +1,110 @@
+use crate::engine::action::Action;
+use crate::engine::world::{BattleSession, GameState, World, MAP_HEIGHT, MAP_WIDTH};
+
+/// Drives a `World` without a terminal, audio or wall-clock timing, so the
+/// game can be scripted from tests and tools.
+///
+/// Things the interactive loop owns stay with the caller: move cooldowns,
+/// the 10s battle penalty (pass it in `Action::BattleOption`) and saving.
+pub struct Headless {
+    world: World,
+    running: bool,
+}
+
+/// The current dialogue page as the player would see it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogueView {
+    pub title: String,
+    pub text: String,
+    pub page: usize, // 0-based
+    pub pages: usize,
+    pub awaiting_choice: bool,
+}
+
+/// Everything observable about a run at one point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub state: GameState,
+    pub room: usize,
+    pub pos: (i32, i32),
+    pub hp: i32,
+    pub max_hp: i32,
+    pub logs: Vec<String>,
+    pub dialogue: Option<DialogueView>,
+    pub battle: Option<BattleSession>,
+}
+
+impl Headless {
+    /// A new run on the same map size as the interactive game.
+    pub fn new(seed: u64) -> Self {
+        Self::with_size(seed, MAP_WIDTH, MAP_HEIGHT)
+    }
+
+    pub fn with_size(seed: u64, width: usize, height: usize) -> Self {
+        Self::from_world(World::new(seed, width, height))
+    }
+
+    /// Picks up an existing world, e.g. one read back with `save::load_world`.
+    pub fn from_world(world: World) -> Self {
+        Self { world, running: true }
+    }
+
+    /// Applies one action. Returns false once the run is over (quit or dead);
+    /// later actions are ignored.
+    pub fn step(&mut self, action: Action) -> bool {
+        if !self.running {
+            return false;
+        }
+        self.running = self.world.apply_action(action) && self.world.player.hp > 0;
+        // Nothing is written to disk here; callers save explicitly if they want to.
+        self.world.autosave_requested = false;
+        self.running
+    }
+
+    /// Applies actions in order, stopping early if the run ends.
+    pub fn run(&mut self, actions: impl IntoIterator<Item = Action>) -> bool {
+        for action in actions {
+            if !self.step(action) {
+                break;
+            }
+        }
+        self.running
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// For setting up scenarios (teleporting, tweaking stats) in tests.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn into_world(self) -> World {
+        self.world
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let w = &self.world;
+        Snapshot {
+            state: w.state.clone(),
+            room: w.current,
+            pos: (w.player.x, w.player.y),
+            hp: w.player.hp,
+            max_hp: w.player.max_hp(),
+            logs: w.logs.iter().cloned().collect(),
+            dialogue: w.dialogue.as_ref().map(|d| DialogueView {
+                title: d.title.clone(),
+                text: d.pages[d.page_index].clone(),
+                page: d.page_index,
+                pages: d.pages.len(),
+                awaiting_choice: d.awaiting.is_some(),
+            }),
+            battle: w.battle.clone(),
+        }
+    }
+}
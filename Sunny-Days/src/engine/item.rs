@@ -0,0 +1,204 @@
+use crate::engine::data::{self, DataFile};
+use crate::engine::entity::{Consumable, EquipSlot, Equipment, Grip};
+use crate::engine::rarity::{Affix, AffixKind};
+
+use serde::{Deserialize, Serialize};
+
+use std::{error::Error, sync::OnceLock};
+
+const ITEM_DATA: &str = "assets/data/items.json";
+const BUILTIN_ITEMS: &str = include_str!("../../assets/data/items.json");
+
+/// Stable item identifier from the data file (e.g. "basic_sword").
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ItemId(String);
+
+impl ItemId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A catalogue entry: the template every copy of the item is cloned from, plus
+/// what the merchant asks for it. Quest items have no price and can't be traded.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Template<T> {
+    #[serde(flatten)]
+    pub item: T,
+    #[serde(default)]
+    pub price: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemRegistry {
+    consumables: Vec<Template<Consumable>>,
+    equipment: Vec<Template<Equipment>>,
+    /// Consumables random chests are filled from.
+    chest_loot: Vec<ItemId>,
+    /// Equipment bases some chests hold instead, rolled with a rarity and affixes.
+    chest_gear: Vec<ItemId>,
+    affixes: Vec<Affix>,
+    /// What the merchant sells, in menu order.
+    shop: Vec<ItemId>,
+    /// Consumable slots before any bags.
+    consumable_slots: usize,
+}
+
+impl ItemRegistry {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let reg: ItemRegistry = serde_json::from_str(json)?;
+        reg.validate()?;
+        Ok(reg)
+    }
+
+    pub fn builtin() -> Self {
+        data::parse_builtin(ITEM_DATA, BUILTIN_ITEMS)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let ids: Vec<&ItemId> = self.consumables.iter().map(|c| &c.item.id)
+            .chain(self.equipment.iter().map(|e| &e.item.id))
+            .collect();
+        for (i, id) in ids.iter().enumerate() {
+            if ids[..i].contains(id) {
+                return Err(format!("duplicate item id `{}`", id.as_str()));
+            }
+        }
+        if let Some(e) = self.equipment.iter().find(|e| e.item.slot != EquipSlot::Sword && e.item.grip != Grip::OneHanded) {
+            return Err(format!("`{}` has a grip but isn't a weapon", e.item.id.as_str()));
+        }
+        if let Some(c) = self.consumables.iter().find(|c| c.item.max_stack == 0) {
+            return Err(format!("`{}` has a max_stack of 0", c.item.id.as_str()));
+        }
+        if let Some(e) = self.equipment.iter().find(|e| e.item.slot != EquipSlot::Bag && e.item.bag_slots > 0) {
+            return Err(format!("`{}` adds bag slots but isn't a bag", e.item.id.as_str()));
+        }
+        if self.chest_loot.is_empty() {
+            return Err("chest_loot is empty".to_string());
+        }
+        for id in &self.chest_loot {
+            if self.consumable(id).is_none() {
+                return Err(format!("chest_loot names `{}`, which isn't a consumable", id.as_str()));
+            }
+        }
+        if self.chest_gear.is_empty() {
+            return Err("chest_gear is empty".to_string());
+        }
+        for id in &self.chest_gear {
+            if self.equipment(id).is_none() {
+                return Err(format!("chest_gear names `{}`, which isn't equipment", id.as_str()));
+            }
+        }
+        for kind in [AffixKind::Prefix, AffixKind::Suffix] {
+            if !self.affixes.iter().any(|a| a.kind == kind) {
+                return Err(format!("no {kind:?} affixes to roll from"));
+            }
+        }
+        for id in &self.shop {
+            if self.price(id).is_none() {
+                return Err(format!("shop sells `{}`, which has no price", id.as_str()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn consumable(&self, id: &ItemId) -> Option<&Template<Consumable>> {
+        self.consumables.iter().find(|c| &c.item.id == id)
+    }
+
+    pub fn equipment(&self, id: &ItemId) -> Option<&Template<Equipment>> {
+        self.equipment.iter().find(|e| &e.item.id == id)
+    }
+
+    pub fn contains(&self, id: &ItemId) -> bool {
+        self.name(id).is_some()
+    }
+
+    /// Display name, for menus that only hold the id.
+    pub fn name(&self, id: &ItemId) -> Option<&str> {
+        self.consumable(id).map(|c| c.item.name.as_str())
+            .or_else(|| self.equipment(id).map(|e| e.item.name.as_str()))
+    }
+
+    /// A fresh copy of a consumable, or None if the id isn't one.
+    pub fn new_consumable(&self, id: &ItemId) -> Option<Consumable> {
+        self.consumable(id).map(|c| c.item.clone())
+    }
+
+    /// A fresh copy of a piece of equipment, or None if the id isn't one.
+    pub fn new_equipment(&self, id: &ItemId) -> Option<Equipment> {
+        self.equipment(id).map(|e| e.item.clone())
+    }
+
+    /// What the merchant charges; None for items it doesn't deal in.
+    pub fn price(&self, id: &ItemId) -> Option<u32> {
+        self.consumable(id).map(|c| c.price)
+            .or_else(|| self.equipment(id).map(|e| e.price))
+            .flatten()
+    }
+
+    /// What the merchant pays: half the price, rounded down.
+    pub fn sell_price(&self, id: &ItemId) -> Option<u32> {
+        self.price(id).map(|p| p / 2)
+    }
+
+    pub fn consumable_slots(&self) -> usize {
+        self.consumable_slots
+    }
+
+    pub fn chest_loot(&self) -> &[ItemId] {
+        &self.chest_loot
+    }
+
+    pub fn chest_gear(&self) -> &[ItemId] {
+        &self.chest_gear
+    }
+
+    pub fn affixes(&self) -> &[Affix] {
+        &self.affixes
+    }
+
+    pub fn shop(&self) -> &[ItemId] {
+        &self.shop
+    }
+}
+
+impl DataFile for ItemRegistry {
+    const WHAT: &'static str = "Item data";
+
+    fn parse(json: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_json(json)
+    }
+}
+
+static REGISTRY: OnceLock<ItemRegistry> = OnceLock::new();
+
+/// The item catalogue, read from `assets/data/items.json` the first time it's needed.
+pub fn registry() -> &'static ItemRegistry {
+    REGISTRY.get_or_init(|| data::load_registry(ITEM_DATA, BUILTIN_ITEMS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_come_from_templates_by_id() {
+        let items = ItemRegistry::builtin();
+        let axe = items.new_equipment(&ItemId::new("willow_axe")).unwrap();
+        assert_eq!((axe.name.as_str(), axe.atk_bonus), ("Willow Axe", 15));
+        assert!(items.new_consumable(&ItemId::new("willow_axe")).is_none());
+
+        // Quest items exist but the merchant won't deal in them.
+        let dagger = ItemId::new("weeping_dagger");
+        assert!(items.contains(&dagger) && items.sell_price(&dagger).is_none());
+
+        let bad = BUILTIN_ITEMS.replace(r#""healing_salve", "iron_sword""#, r#""healing_salve", "weeping_dagger""#);
+        assert!(ItemRegistry::from_json(&bad).is_err());
+    }
+}
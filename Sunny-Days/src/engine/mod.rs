@@ -1,4 +1,22 @@
 pub mod action;
+pub mod ai;
+pub mod combat;
+pub mod data;
+pub mod dialogue;
+pub mod door;
 pub mod entity;
 pub mod game_loop;
+pub mod headless;
+pub mod item;
+pub mod npc;
+pub mod quest;
+pub mod rarity;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod shop;
+pub mod skill;
+pub mod stats;
+pub mod status;
 pub mod world;
+pub mod xp;
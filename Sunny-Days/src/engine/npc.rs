@@ -0,0 +1,175 @@
+use crate::engine::combat::EnemyStats;
+use crate::engine::data::{self, DataFile};
+use crate::engine::dialogue::DialogueGraph;
+use crate::engine::door;
+use crate::engine::item;
+use crate::engine::quest;
+
+use serde::{Deserialize, Serialize};
+
+use std::{error::Error, sync::OnceLock};
+
+const NPC_DATA: &str = "assets/data/npcs.json";
+const BUILTIN_NPCS: &str = include_str!("../../assets/data/npcs.json");
+
+/// Stable NPC identifier from the data file (e.g. "mayor_sol").
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NpcId(String);
+
+impl NpcId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NpcColor {
+    White,
+    Yellow,
+    Cyan,
+    Magenta,
+    LightBlue,
+    Red,
+    Green,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Placement {
+    /// Offset from the player's spawn, nudged onto a nearby floor tile if needed.
+    NearSpawn { dx: i32, dy: i32 },
+    /// Random floor tile at least `NPC_MIN_SEP` away from everything placed so far.
+    Spaced,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NpcDef {
+    pub id: NpcId,
+    pub name: String,
+    pub symbol: char,
+    pub color: NpcColor,
+    pub room: usize,
+    pub placement: Placement,
+    /// Present for NPCs that can be fought.
+    #[serde(default)]
+    pub combat: Option<EnemyStats>,
+    pub dialogue: DialogueGraph,
+}
+
+impl NpcDef {
+    /// Flag the engine sets when this NPC is beaten in battle.
+    pub fn defeated_flag(&self) -> String {
+        defeated_flag(&self.id)
+    }
+}
+
+pub fn defeated_flag(id: &NpcId) -> String {
+    format!("{}_defeated", id.as_str())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NpcRegistry {
+    npcs: Vec<NpcDef>,
+}
+
+impl NpcRegistry {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let reg: NpcRegistry = serde_json::from_str(json)?;
+        reg.validate()?;
+        Ok(reg)
+    }
+
+    pub fn builtin() -> Self {
+        data::parse_builtin(NPC_DATA, BUILTIN_NPCS)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut reads = Vec::new();
+        let mut known: Vec<String> = self.npcs.iter().map(|d| d.defeated_flag()).collect();
+        let quests = quest::registry();
+        let items = item::registry();
+        known.extend(quests.flags_set().into_iter().map(str::to_string));
+
+        for (i, def) in self.npcs.iter().enumerate() {
+            if self.npcs[..i].iter().any(|d| d.id == def.id) {
+                return Err(format!("duplicate npc id `{}`", def.id.as_str()));
+            }
+            if def.room > 1 {
+                return Err(format!("npc `{}` is in room {}, only rooms 0 and 1 exist", def.id.as_str(), def.room));
+            }
+            if def.combat.is_none() && def.dialogue.starts_battle() {
+                return Err(format!("npc `{}` can start a battle but has no combat stats", def.id.as_str()));
+            }
+            if let Some(stats) = &def.combat {
+                stats.ai.validate().map_err(|e| format!("npc `{}` ai: {e}", def.id.as_str()))?;
+                let drops = stats.drops.iter().chain(stats.allies.iter().flat_map(|a| &a.stats.drops));
+                for drop in drops {
+                    if items.consumable(&drop.item).is_none() {
+                        return Err(format!("npc `{}` drops `{}`, which isn't a consumable", def.id.as_str(), drop.item.as_str()));
+                    }
+                }
+                for ally in &stats.allies {
+                    if !ally.stats.allies.is_empty() {
+                        return Err(format!("npc `{}`: ally `{}` can't bring allies of its own", def.id.as_str(), ally.name));
+                    }
+                    ally.stats.ai.validate().map_err(|e| format!("npc `{}` ally `{}` ai: {e}", def.id.as_str(), ally.name))?;
+                }
+            }
+            if let Some(id) = def.dialogue.item_refs().into_iter().find(|id| !items.contains(id)) {
+                return Err(format!("npc `{}` names item `{}`, which isn't in the catalogue", def.id.as_str(), id.as_str()));
+            }
+            let (r, s) = def.dialogue.validate().map_err(|e| format!("npc `{}`: {e}", def.id.as_str()))?;
+            reads.extend(r.into_iter().map(|f| (def.id.as_str(), f)));
+            known.extend(s.into_iter().map(str::to_string));
+        }
+
+        for (q, id) in quests.item_refs() {
+            if !items.contains(id) {
+                return Err(format!("quest `{q}` names item `{}`, which isn't in the catalogue", id.as_str()));
+            }
+        }
+        for (q, id) in quests.npc_refs() {
+            if self.get(id).is_none() {
+                return Err(format!("quest `{q}` names npc `{}`, which doesn't exist", id.as_str()));
+            }
+        }
+
+        // A flag nothing ever sets is almost certainly a typo.
+        for (npc, flag) in reads {
+            if !known.iter().any(|k| k == flag) {
+                return Err(format!("npc `{npc}` tests flag `{flag}`, which nothing sets"));
+            }
+        }
+        for flag in door::registry().flags() {
+            if !known.iter().any(|k| k == flag) {
+                return Err(format!("a door rule tests flag `{flag}`, which nothing sets"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn all(&self) -> &[NpcDef] {
+        &self.npcs
+    }
+
+    pub fn get(&self, id: &NpcId) -> Option<&NpcDef> {
+        self.npcs.iter().find(|d| &d.id == id)
+    }
+}
+
+impl DataFile for NpcRegistry {
+    const WHAT: &'static str = "NPC data";
+
+    fn parse(json: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_json(json)
+    }
+}
+
+static REGISTRY: OnceLock<NpcRegistry> = OnceLock::new();
+
+/// NPC definitions, read from `assets/data/npcs.json` the first time they're needed.
+pub fn registry() -> &'static NpcRegistry {
+    REGISTRY.get_or_init(|| data::load_registry(NPC_DATA, BUILTIN_NPCS))
+}
@@ -0,0 +1,143 @@
+use crate::engine::data::{self, DataFile};
+use crate::engine::dialogue::{Condition, Effect};
+use crate::engine::item::ItemId;
+use crate::engine::npc::NpcId;
+
+use serde::{Deserialize, Serialize};
+
+use std::{error::Error, sync::OnceLock};
+
+const QUEST_DATA: &str = "assets/data/quests.json";
+const BUILTIN_QUESTS: &str = include_str!("../../assets/data/quests.json");
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// Talk to this NPC while the stage is active.
+    TalkTo(NpcId),
+    /// Beat this NPC in battle (its `<id>_defeated` flag is set).
+    Defeat(NpcId),
+    /// Hold an item with this id, equipped or in the backpack.
+    Fetch(ItemId),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Stage {
+    pub description: String,
+    pub objectives: Vec<Objective>,
+    /// Run once every objective in the stage is met.
+    #[serde(default)]
+    pub on_complete: Vec<Effect>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuestDef {
+    pub id: String,
+    pub title: String,
+    /// The quest appears in the journal once all of these hold.
+    #[serde(default)]
+    pub start_when: Vec<Condition>,
+    /// If these hold the quest counts as finished without re-running rewards
+    /// (keeps older saves from being handed rewards twice).
+    #[serde(default)]
+    pub done_when: Vec<Condition>,
+    pub stages: Vec<Stage>,
+    #[serde(default)]
+    pub rewards: Vec<Effect>,
+}
+
+/// Per-quest state stored on `World`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuestProgress {
+    pub stage: usize,
+    pub objectives_done: Vec<bool>, // for the current stage
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuestRegistry {
+    quests: Vec<QuestDef>,
+}
+
+impl QuestRegistry {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let reg: QuestRegistry = serde_json::from_str(json)?;
+        reg.validate()?;
+        Ok(reg)
+    }
+
+    pub fn builtin() -> Self {
+        data::parse_builtin(QUEST_DATA, BUILTIN_QUESTS)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, q) in self.quests.iter().enumerate() {
+            if self.quests[..i].iter().any(|o| o.id == q.id) {
+                return Err(format!("duplicate quest id `{}`", q.id));
+            }
+            if q.stages.is_empty() {
+                return Err(format!("quest `{}` has no stages", q.id));
+            }
+            if let Some(s) = q.stages.iter().find(|s| s.objectives.is_empty()) {
+                return Err(format!("quest `{}` stage \"{}\" has no objectives", q.id, s.description));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn all(&self) -> &[QuestDef] {
+        &self.quests
+    }
+
+    /// Flags set by stage completions and rewards, so NPC data may test them.
+    pub fn flags_set(&self) -> Vec<&str> {
+        self.quests.iter()
+            .flat_map(|q| q.stages.iter().flat_map(|s| &s.on_complete).chain(&q.rewards))
+            .filter_map(|e| match e {
+                Effect::SetFlag(f) => Some(f.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// NPC ids named by objectives, for cross-checking against the NPC data.
+    pub fn npc_refs(&self) -> Vec<(&str, &NpcId)> {
+        self.quests.iter()
+            .flat_map(|q| q.stages.iter().flat_map(move |s| s.objectives.iter().map(move |o| (q.id.as_str(), o))))
+            .filter_map(|(q, o)| match o {
+                Objective::TalkTo(id) | Objective::Defeat(id) => Some((q, id)),
+                Objective::Fetch(_) => None,
+            })
+            .collect()
+    }
+
+    /// Item ids named by fetch objectives, conditions and effects, for checking against the catalogue.
+    pub fn item_refs(&self) -> Vec<(&str, &ItemId)> {
+        self.quests.iter()
+            .flat_map(|q| {
+                let fetched = q.stages.iter().flat_map(|s| &s.objectives).filter_map(|o| match o {
+                    Objective::Fetch(id) => Some(id),
+                    _ => None,
+                });
+                let conds = q.start_when.iter().chain(&q.done_when).filter_map(Condition::item);
+                let effects = q.stages.iter().flat_map(|s| &s.on_complete).chain(&q.rewards).filter_map(Effect::item);
+                fetched.chain(conds).chain(effects).map(move |id| (q.id.as_str(), id))
+            })
+            .collect()
+    }
+}
+
+impl DataFile for QuestRegistry {
+    const WHAT: &'static str = "Quest data";
+
+    fn parse(json: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_json(json)
+    }
+}
+
+static REGISTRY: OnceLock<QuestRegistry> = OnceLock::new();
+
+/// Quest definitions, read from `assets/data/quests.json` the first time they're needed.
+pub fn registry() -> &'static QuestRegistry {
+    REGISTRY.get_or_init(|| data::load_registry(QUEST_DATA, BUILTIN_QUESTS))
+}
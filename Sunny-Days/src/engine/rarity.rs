@@ -0,0 +1,160 @@
+use crate::engine::entity::Equipment;
+use crate::engine::item::ItemRegistry;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    pub const ALL: [Rarity; 5] = [Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Epic, Rarity::Legendary];
+
+    /// Relative odds of a rolled item landing on this tier.
+    fn weight(self) -> u32 {
+        match self {
+            Rarity::Common => 60,
+            Rarity::Uncommon => 25,
+            Rarity::Rare => 10,
+            Rarity::Epic => 4,
+            Rarity::Legendary => 1,
+        }
+    }
+
+    /// How many affixes an item of this tier carries.
+    fn affixes(self) -> usize {
+        match self {
+            Rarity::Common => 0,
+            Rarity::Uncommon => 1,
+            Rarity::Rare | Rarity::Epic | Rarity::Legendary => 2,
+        }
+    }
+
+    /// Multiplier on affix bonuses.
+    fn power(self) -> i32 {
+        match self {
+            Rarity::Common | Rarity::Uncommon | Rarity::Rare => 1,
+            Rarity::Epic => 2,
+            Rarity::Legendary => 3,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Rarity::Common => "Common",
+            Rarity::Uncommon => "Uncommon",
+            Rarity::Rare => "Rare",
+            Rarity::Epic => "Epic",
+            Rarity::Legendary => "Legendary",
+        }
+    }
+
+    pub fn roll<R: Rng + ?Sized>(rng: &mut R) -> Rarity {
+        let total: u32 = Self::ALL.iter().map(|r| r.weight()).sum();
+        let mut pick = rng.gen_range(0..total);
+        for r in Self::ALL {
+            if pick < r.weight() {
+                return r;
+            }
+            pick -= r.weight();
+        }
+        Rarity::Common
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AffixKind {
+    Prefix, // "Keen Iron Sword"
+    Suffix, // "Iron Sword of the Fox"
+}
+
+/// A random modifier from `assets/data/items.json`, rolled onto chest gear.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Affix {
+    pub name: String,
+    pub kind: AffixKind,
+    #[serde(default)]
+    pub hp_bonus: i32,
+    #[serde(default)]
+    pub atk_bonus: i32,
+    #[serde(default)]
+    pub def_bonus: i32,
+    #[serde(default)]
+    pub speed_bonus: i32,
+}
+
+/// Rolls a piece of chest gear: one of the catalogue's `chest_gear` bases, a
+/// rarity, and that many affixes.
+pub fn roll_equipment<R: Rng + ?Sized>(items: &ItemRegistry, rng: &mut R) -> Equipment {
+    let bases = items.chest_gear();
+    let base = items.new_equipment(&bases[rng.gen_range(0..bases.len())])
+        .expect("chest gear is checked against the catalogue");
+    let rarity = Rarity::roll(rng);
+    enchant(base, rarity, items, rng)
+}
+
+/// Gives `base` the affixes its new rarity calls for and names it after them.
+fn enchant<R: Rng + ?Sized>(mut eq: Equipment, rarity: Rarity, items: &ItemRegistry, rng: &mut R) -> Equipment {
+    let kinds: &[AffixKind] = match rarity.affixes() {
+        0 => &[],
+        1 if rng.gen_bool(0.5) => &[AffixKind::Prefix],
+        1 => &[AffixKind::Suffix],
+        _ => &[AffixKind::Prefix, AffixKind::Suffix],
+    };
+    let power = rarity.power();
+    let mut name = eq.name.clone();
+    for &kind in kinds {
+        let pool: Vec<&Affix> = items.affixes().iter().filter(|a| a.kind == kind).collect();
+        let affix = pool[rng.gen_range(0..pool.len())];
+        eq.hp_bonus += affix.hp_bonus * power;
+        eq.atk_bonus += affix.atk_bonus * power;
+        eq.def_bonus += affix.def_bonus * power;
+        eq.speed_bonus += affix.speed_bonus * power;
+        name = match kind {
+            AffixKind::Prefix => format!("{} {}", affix.name, name),
+            AffixKind::Suffix => format!("{} {}", name, affix.name),
+        };
+    }
+    eq.name = name;
+    eq.rarity = rarity;
+    eq
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::item::ItemId;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn affixes_rename_and_scale_with_rarity() {
+        let items = ItemRegistry::builtin();
+        let sword = items.new_equipment(&ItemId::new("iron_sword")).unwrap();
+
+        let common = enchant(sword.clone(), Rarity::Common, &items, &mut StdRng::seed_from_u64(1));
+        assert_eq!((common.name.as_str(), common.atk_bonus), ("Iron Sword", 6));
+
+        let legendary = enchant(sword.clone(), Rarity::Legendary, &items, &mut StdRng::seed_from_u64(1));
+        assert_eq!(legendary.rarity, Rarity::Legendary);
+        assert_eq!(legendary.id, sword.id);
+        assert!(legendary.name.contains(" Iron Sword of "));
+        let gained = |e: &Equipment| e.hp_bonus + e.atk_bonus + e.def_bonus + e.speed_bonus;
+        let rare = enchant(sword.clone(), Rarity::Rare, &items, &mut StdRng::seed_from_u64(1));
+        assert_eq!(gained(&legendary) - gained(&sword), 3 * (gained(&rare) - gained(&sword)));
+
+        let a = roll_equipment(&items, &mut StdRng::seed_from_u64(7));
+        let b = roll_equipment(&items, &mut StdRng::seed_from_u64(7));
+        assert_eq!((a.name, a.atk_bonus, a.rarity), (b.name, b.atk_bonus, b.rarity));
+    }
+}
@@ -0,0 +1,295 @@
+use crate::engine::action::Action;
+use crate::engine::world::{GameState, World};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// Bumped when the replay file layout changes (not when the engine does;
+/// engine changes show up as divergences instead).
+pub const REPLAY_VERSION: u32 = 1;
+
+const REPLAY_DIR: &str = "replays";
+
+/// The bits of state compared after every recorded action to spot divergence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub state: GameState,
+    pub room: usize,
+    pub pos: (i32, i32),
+    pub hp: i32,
+    pub enemy_hp: Option<i32>, // summed over the whole group
+    pub dialogue: Option<(Option<String>, usize)>, // node, page
+}
+
+impl Checkpoint {
+    pub fn of(world: &World) -> Self {
+        Self {
+            state: world.state.clone(),
+            room: world.current,
+            pos: (world.player.x, world.player.y),
+            hp: world.player.hp,
+            enemy_hp: world.battle.as_ref().map(|b| b.enemies.iter().map(|e| e.hp.max(0)).sum()),
+            dialogue: world.dialogue.as_ref().map(|d| (d.node.clone(), d.page_index)),
+        }
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} room {} at ({}, {}) hp {}", self.state, self.room + 1, self.pos.0, self.pos.1, self.hp)?;
+        if let Some(hp) = self.enemy_hp { write!(f, ", enemy hp {}", hp)?; }
+        if let Some((node, page)) = &self.dialogue {
+            write!(f, ", dialogue {} page {}", node.as_deref().unwrap_or("-"), page + 1)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    pub tick: u64,   // index of the apply_action call this came from
+    pub ms: u64,     // time since the recording started
+    pub action: Action,
+    pub check: Checkpoint, // state right after the action
+}
+
+/// A recorded session: the seed and map size to rebuild the world, and every
+/// non-idle action fed to it. Idle ticks in between are replayed as `Action::None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub width: usize,
+    pub height: usize,
+    pub entries: Vec<ReplayEntry>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "replay file I/O: {e}"),
+            ReplayError::Format(e) => write!(f, "replay file is malformed: {e}"),
+            ReplayError::UnsupportedVersion(v) => {
+                write!(f, "replay version {v} is not supported (expected {REPLAY_VERSION})")
+            }
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let json = fs::read_to_string(path).map_err(ReplayError::Io)?;
+        let replay: Replay = serde_json::from_str(&json).map_err(ReplayError::Format)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(ReplayError::Io)?;
+        }
+        let json = serde_json::to_string(self).map_err(ReplayError::Format)?;
+        fs::write(path, json).map_err(ReplayError::Io)
+    }
+
+    /// Where sessions are recorded when no `--record` path is given.
+    pub fn default_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(REPLAY_DIR).join("last.json")
+    }
+
+    /// The world the recording started from.
+    pub fn fresh_world(&self) -> World {
+        World::new(self.seed, self.width, self.height)
+    }
+}
+
+/// Collects actions as the game loop applies them.
+pub struct Recorder {
+    replay: Replay,
+    tick: u64,
+    started: Instant,
+}
+
+impl Recorder {
+    /// Must be created before the first action is applied to `world`.
+    pub fn new(world: &World) -> Self {
+        let map = &world.levels[0].map;
+        Self {
+            replay: Replay {
+                version: REPLAY_VERSION,
+                seed: world.seed,
+                width: map.width,
+                height: map.height,
+                entries: Vec::new(),
+            },
+            tick: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Call once per `apply_action`, after it ran.
+    pub fn record(&mut self, action: Action, world: &World) {
+        if !matches!(action, Action::None) {
+            self.replay.entries.push(ReplayEntry {
+                tick: self.tick,
+                ms: self.started.elapsed().as_millis() as u64,
+                action,
+                check: Checkpoint::of(world),
+            });
+        }
+        self.tick += 1;
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+/// The first recorded action whose outcome doesn't match the recording.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub entry: usize,
+    pub tick: u64,
+    pub action: Action,
+    pub expected: Checkpoint,
+    pub actual: Checkpoint,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "diverged at tick {} (action #{}, {:?}): expected {}, got {}",
+            self.tick, self.entry + 1, self.action, self.expected, self.actual,
+        )
+    }
+}
+
+/// Feeds a replay's actions into a world one entry at a time.
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+    tick: u64,
+    running: bool,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0, tick: 0, running: true }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn is_done(&self) -> bool {
+        !self.running || self.next >= self.replay.entries.len()
+    }
+
+    /// Entries not yet played.
+    pub fn remaining(&self) -> usize {
+        self.replay.entries.len() - self.next
+    }
+
+    /// Recording time of the next entry, for pacing playback.
+    pub fn next_ms(&self) -> Option<u64> {
+        if self.is_done() { None } else { Some(self.replay.entries[self.next].ms) }
+    }
+
+    /// Applies the idle ticks leading up to the next entry, then the entry itself.
+    pub fn step(&mut self, world: &mut World) -> Result<(), Box<Divergence>> {
+        let Some(entry) = self.replay.entries.get(self.next).filter(|_| self.running) else { return Ok(()) };
+
+        while self.tick < entry.tick && self.running {
+            self.running = world.apply_action(Action::None);
+            self.tick += 1;
+        }
+        self.running = self.running && world.apply_action(entry.action) && world.player.hp > 0;
+        self.tick += 1;
+        world.autosave_requested = false;
+
+        let index = self.next;
+        self.next += 1;
+
+        let actual = Checkpoint::of(world);
+        if actual != entry.check {
+            return Err(Box::new(Divergence {
+                entry: index,
+                tick: entry.tick,
+                action: entry.action,
+                expected: entry.check.clone(),
+                actual,
+            }));
+        }
+        Ok(())
+    }
+}
+
+/// Replays a whole recording without a terminal and reports the first divergence.
+pub fn verify(replay: &Replay) -> Result<World, Box<Divergence>> {
+    let mut world = replay.fresh_world();
+    let mut playback = Playback::new(replay.clone());
+    while !playback.is_done() {
+        playback.step(&mut world)?;
+    }
+    Ok(world)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Replay {
+        let mut world = World::new(21, 40, 24);
+        let mut rec = Recorder::new(&world);
+        let actions = [
+            Action::Confirm, Action::None, Action::Confirm,
+            Action::Move(1, 0), Action::None, Action::None, Action::Move(0, 1),
+            Action::ToggleStats, Action::ToggleStats, Action::Move(-1, 0),
+        ];
+        for a in actions {
+            world.apply_action(a);
+            rec.record(a, &world);
+        }
+        rec.finish()
+    }
+
+    #[test]
+    fn idle_ticks_are_not_stored() {
+        let replay = session();
+        assert_eq!(replay.entries.len(), 7);
+        assert_eq!(replay.entries[2].tick, 3);
+    }
+
+    #[test]
+    fn recording_plays_back_cleanly() {
+        let replay = session();
+        let world = verify(&replay).unwrap();
+        assert_eq!(Checkpoint::of(&world), replay.entries.last().unwrap().check);
+    }
+
+    #[test]
+    fn changed_outcome_is_reported() {
+        let mut replay = session();
+        replay.entries[3].check.hp += 1;
+        let Err(d) = verify(&replay) else { panic!("expected a divergence") };
+        assert_eq!((d.entry, d.tick), (3, 6));
+    }
+}
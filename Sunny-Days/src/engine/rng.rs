@@ -0,0 +1,48 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+/// Runtime randomness for a run (deflects, fleeing). Owned by `World` and
+/// saved with it, so the same seed and the same actions replay identically.
+///
+/// SplitMix64: a single `u64` of state, which keeps save files readable and
+/// doesn't depend on `StdRng`'s algorithm staying the same between rand releases.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl GameRng {
+    // Keeps the combat stream apart from the map generator, which uses the raw seed.
+    const STREAM: u64 = 0xC0FF_EE00_BA77_1E5D;
+
+    /// The generator a fresh world with this seed starts with.
+    pub fn for_world(seed: u64) -> Self {
+        Self { state: seed ^ Self::STREAM }
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,865 @@
+use crate::engine::rng::GameRng;
+use crate::engine::world::World;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Bumped whenever the on-disk layout of `World` changes. Every bump needs a
+/// matching entry in `MIGRATIONS` and a fixture in `tests/fixtures/saves/`.
+pub const SAVE_VERSION: u32 = 18;
+
+type Migration = fn(Value) -> Result<Value, SaveError>;
+
+/// `MIGRATIONS[i]` upgrades a version `i + 1` save to version `i + 2`.
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2, migrate_v2_to_v3, migrate_v3_to_v4, migrate_v4_to_v5, migrate_v5_to_v6, migrate_v6_to_v7, migrate_v7_to_v8, migrate_v8_to_v9, migrate_v9_to_v10, migrate_v10_to_v11, migrate_v11_to_v12, migrate_v12_to_v13, migrate_v13_to_v14, migrate_v14_to_v15, migrate_v15_to_v16, migrate_v16_to_v17, migrate_v17_to_v18];
+
+/// Number of manual save slots shown on the title screen.
+pub const SLOT_COUNT: usize = 3;
+
+const SAVE_DIR: &str = "saves";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveSlot {
+    Manual(usize), // 1-based
+    Autosave,
+}
+
+impl SaveSlot {
+    pub fn label(&self) -> String {
+        match self {
+            SaveSlot::Manual(n) => format!("Slot {}", n),
+            SaveSlot::Autosave => "Autosave".to_string(),
+        }
+    }
+
+    fn file_name(&self) -> String {
+        match self {
+            SaveSlot::Manual(n) => format!("slot{}.json", n),
+            SaveSlot::Autosave => "autosave.json".to_string(),
+        }
+    }
+
+    /// Every slot in the order the title screen lists them.
+    pub fn all() -> Vec<SaveSlot> {
+        let mut slots: Vec<SaveSlot> = (1..=SLOT_COUNT).map(SaveSlot::Manual).collect();
+        slots.push(SaveSlot::Autosave);
+        slots
+    }
+}
+
+/// Small header stored alongside the world so slots can be listed cheaply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotMeta {
+    pub saved_at: u64, // unix seconds
+    pub seed: u64,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub room: usize,
+    pub progress: String,
+}
+
+impl SlotMeta {
+    fn from_world(world: &World) -> Self {
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            saved_at,
+            seed: world.seed,
+            hp: world.player.hp,
+            max_hp: world.player.max_hp(),
+            room: world.current,
+            progress: world.progress_summary(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SlotInfo {
+    pub slot: SaveSlot,
+    pub meta: Option<SlotMeta>, // None = empty or unreadable
+    pub error: Option<String>,  // set when the file exists but can't be read
+}
+
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u32,
+    meta: SlotMeta,
+    world: &'a World,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    version: u32,
+    world: World,
+}
+
+#[derive(Deserialize)]
+struct SaveHeader {
+    meta: SlotMeta,
+}
+
+fn read_header(json: &str) -> Result<SlotMeta, SaveError> {
+    let (value, original) = migrate_to_current(json)?;
+    let header: SaveHeader = from_migrated(value, original)?;
+    Ok(header.meta)
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+    /// A migration step needed `field` but it was missing or had the wrong shape.
+    Migration { version: u32, field: String },
+    /// The fully migrated save still didn't match the current `World` layout.
+    Schema { version: u32, field: String, reason: String },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "save file I/O failed: {e}"),
+            SaveError::Format(e) => write!(f, "save file is malformed: {e}"),
+            SaveError::UnsupportedVersion(v) => {
+                write!(f, "save version {v} is not supported (expected {SAVE_VERSION})")
+            }
+            SaveError::Migration { version, field } => {
+                write!(f, "cannot migrate save from version {version}: field `{field}` is missing or invalid")
+            }
+            SaveError::Schema { version, field, reason } => {
+                write!(f, "save (originally version {version}) has a bad `{field}`: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Format(e)
+    }
+}
+
+/// Owns the save directory and maps slots to files inside it.
+pub struct SlotManager {
+    dir: PathBuf,
+}
+
+impl SlotManager {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Save directory next to the crate, like the audio assets.
+    pub fn default_dir() -> Self {
+        Self::new(Path::new(env!("CARGO_MANIFEST_DIR")).join(SAVE_DIR))
+    }
+
+    pub fn path_for(&self, slot: SaveSlot) -> PathBuf {
+        self.dir.join(slot.file_name())
+    }
+
+    pub fn save(&self, slot: SaveSlot, world: &World) -> Result<(), SaveError> {
+        save_world(world, &self.path_for(slot))
+    }
+
+    pub fn load(&self, slot: SaveSlot) -> Result<World, SaveError> {
+        load_world(&self.path_for(slot))
+    }
+
+    /// Reads only the header of each slot; the world itself is skipped.
+    pub fn list(&self) -> Vec<SlotInfo> {
+        SaveSlot::all()
+            .into_iter()
+            .map(|slot| match fs::read_to_string(self.path_for(slot)) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    SlotInfo { slot, meta: None, error: None }
+                }
+                Err(e) => SlotInfo { slot, meta: None, error: Some(e.to_string()) },
+                Ok(json) => match read_header(&json) {
+                    Ok(meta) => SlotInfo { slot, meta: Some(meta), error: None },
+                    Err(e) => SlotInfo { slot, meta: None, error: Some(e.to_string()) },
+                },
+            })
+            .collect()
+    }
+}
+
+pub fn save_world(world: &World, path: &Path) -> Result<(), SaveError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = SaveFileRef { version: SAVE_VERSION, meta: SlotMeta::from_world(world), world };
+    let json = serde_json::to_string(&file)?;
+
+    // Write to a temp file first so a crash mid-write can't eat the old save.
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub fn load_world(path: &Path) -> Result<World, SaveError> {
+    let json = fs::read_to_string(path)?;
+    load_world_from_str(&json)
+}
+
+/// Parses a save of any known version, migrating it up to `SAVE_VERSION`.
+pub fn load_world_from_str(json: &str) -> Result<World, SaveError> {
+    let (value, original) = migrate_to_current(json)?;
+    let file: SaveFile = from_migrated(value, original)?;
+    debug_assert_eq!(file.version, SAVE_VERSION);
+    Ok(file.world)
+}
+
+/// Deserializes upgraded JSON, reporting the exact field path on failure.
+fn from_migrated<T: DeserializeOwned>(value: Value, original: u32) -> Result<T, SaveError> {
+    serde_path_to_error::deserialize(value).map_err(|e| SaveError::Schema {
+        version: original,
+        field: e.path().to_string(),
+        reason: e.inner().to_string(),
+    })
+}
+
+/// Runs the migration chain; returns the upgraded JSON and the version it started at.
+fn migrate_to_current(json: &str) -> Result<(Value, u32), SaveError> {
+    let mut value: Value = serde_json::from_str(json)?;
+
+    let original = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| SaveError::Migration { version: 0, field: "version".to_string() })?
+        as u32;
+    if original == 0 || original > SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(original));
+    }
+
+    for version in original..SAVE_VERSION {
+        value = MIGRATIONS[(version - 1) as usize](value)?;
+        value["version"] = json!(version + 1);
+    }
+    Ok((value, original))
+}
+
+/// Looks up a dotted path like `world.npcs.0.id`, naming it in the error if absent.
+fn field<'a>(value: &'a Value, path: &str, version: u32) -> Result<&'a Value, SaveError> {
+    path.split('.')
+        .try_fold(value, |v, key| match key.parse::<usize>() {
+            Ok(i) if v.is_array() => v.get(i),
+            _ => v.get(key),
+        })
+        .ok_or_else(|| SaveError::Migration { version, field: path.to_string() })
+}
+
+fn field_i64(value: &Value, path: &str, version: u32) -> Result<i64, SaveError> {
+    field(value, path, version)?
+        .as_i64()
+        .ok_or_else(|| SaveError::Migration { version, field: path.to_string() })
+}
+
+/// v2 added the slot header (`meta`) next to the world.
+fn migrate_v1_to_v2(mut save: Value) -> Result<Value, SaveError> {
+    const STORY_FLAGS: [&str; 9] = [
+        "mayor_done", "noor_done", "lamp_done",
+        "dorosht_accepted", "dorosht_completed",
+        "shab_defeated", "krad_defeated", "mah_defeated", "mayor_defeated",
+    ];
+
+    let mut done = 0;
+    for flag in STORY_FLAGS {
+        let path = format!("world.{flag}");
+        let set = field(&save, &path, 1)?
+            .as_bool()
+            .ok_or(SaveError::Migration { version: 1, field: path })?;
+        if set { done += 1; }
+    }
+
+    let meta = json!({
+        "saved_at": 0,
+        "seed": field(&save, "world.seed", 1)?.as_u64()
+            .ok_or_else(|| SaveError::Migration { version: 1, field: "world.seed".to_string() })?,
+        "hp": field_i64(&save, "world.player.hp", 1)?,
+        "max_hp": field_i64(&save, "world.player.max_hp", 1)?,
+        "room": field_i64(&save, "world.current", 1)?,
+        "progress": format!("Story {}/{}", done, STORY_FLAGS.len()),
+    });
+    save["meta"] = meta;
+    Ok(save)
+}
+
+/// v3 replaced the `NpcId` enum with string ids from `assets/data/npcs.json`,
+/// and dialogues now carry their own `on_close` instead of re-deriving it.
+fn migrate_v2_to_v3(mut save: Value) -> Result<Value, SaveError> {
+    fn new_id(old: &Value, path: &str) -> Result<Value, SaveError> {
+        let id = match old.as_str() {
+            Some("MayorSol") => "mayor_sol",
+            Some("Noor") => "noor",
+            Some("Lamp") => "lamp",
+            Some("Dorosht") => "dorosht",
+            Some("Random1") => "villager_1",
+            Some("Random2") => "villager_2",
+            Some("Random3") => "villager_3",
+            Some("Weeping1") => "weeping_1",
+            Some("Weeping2") => "weeping_2",
+            Some("Weeping3") => "weeping_3",
+            Some("Weeping4") => "weeping_4",
+            Some("Shab") => "shab",
+            Some("Krad") => "krad",
+            Some("Mah") => "mah",
+            _ => return Err(SaveError::Migration { version: 2, field: path.to_string() }),
+        };
+        Ok(json!(id))
+    }
+    let flag = |name: &str| -> Result<bool, SaveError> {
+        let path = format!("world.{name}");
+        field(&save, &path, 2)?.as_bool().ok_or(SaveError::Migration { version: 2, field: path })
+    };
+    let (shab, krad, mah) = (flag("shab_defeated")?, flag("krad_defeated")?, flag("mah_defeated")?);
+    let (mayor, dorosht) = (flag("mayor_defeated")?, flag("dorosht_completed")?);
+
+    let npcs = field(&save, "world.npcs", 2)?
+        .as_array()
+        .ok_or_else(|| SaveError::Migration { version: 2, field: "world.npcs".to_string() })?
+        .len();
+    for i in 0..npcs {
+        let path = format!("world.npcs.{i}.id");
+        save["world"]["npcs"][i]["id"] = new_id(field(&save, &path, 2)?, &path)?;
+    }
+
+    if !save["world"]["battle"].is_null() {
+        let path = "world.battle.enemy_id";
+        save["world"]["battle"]["enemy_id"] = new_id(field(&save, path, 2)?, path)?;
+    }
+
+    if !save["world"]["dialogue"].is_null() {
+        let old = field(&save, "world.dialogue.npc", 2)?.clone();
+        let title = field(&save, "world.dialogue.title", 2)?.as_str().unwrap_or_default().to_string();
+        let is_chest = save["world"]["dialogue"]["awaiting"].get("Chest").is_some();
+
+        // Chests and post-battle lines used to borrow an arbitrary NPC id.
+        let npc = if is_chest || (old == "Random1" && title != "Villager") {
+            Value::Null
+        } else {
+            new_id(&old, "world.dialogue.npc")?
+        };
+        let on_close = match npc.as_str() {
+            Some("shab") if !shab => json!("battle"),
+            Some("krad") if !krad => json!("battle"),
+            Some("mah") if !mah && shab && krad => json!("battle"),
+            Some("mayor_sol") if !mayor && dorosht && mah => json!("battle"),
+            Some("mayor_sol") if mayor => json!("fin"),
+            _ => Value::Null,
+        };
+        save["world"]["dialogue"]["npc"] = npc;
+        save["world"]["dialogue"]["on_close"] = on_close;
+    }
+    Ok(save)
+}
+
+/// v4 moved the story booleans into a named flag store and dialogues onto
+/// nodes of each NPC's dialogue graph.
+fn migrate_v3_to_v4(mut save: Value) -> Result<Value, SaveError> {
+    const RENAMED: [(&str, &str); 9] = [
+        ("mayor_done", "mayor_done"),
+        ("noor_done", "noor_done"),
+        ("lamp_done", "lamp_done"),
+        ("dorosht_accepted", "dorosht_accepted"),
+        ("dorosht_completed", "dorosht_completed"),
+        ("shab_defeated", "shab_defeated"),
+        ("krad_defeated", "krad_defeated"),
+        ("mah_defeated", "mah_defeated"),
+        ("mayor_defeated", "mayor_sol_defeated"),
+    ];
+
+    let mut flags = Vec::new();
+    for (old, new) in RENAMED {
+        let path = format!("world.{old}");
+        let set = field(&save, &path, 3)?
+            .as_bool()
+            .ok_or(SaveError::Migration { version: 3, field: path })?;
+        if set { flags.push(json!(new)); }
+    }
+    let world = save["world"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 3, field: "world".to_string() })?;
+    for (old, _) in RENAMED {
+        world.remove(old);
+    }
+    world.insert("flags".to_string(), Value::Array(flags));
+
+    if !save["world"]["dialogue"].is_null() {
+        let npc = field(&save, "world.dialogue.npc", 3)?.as_str().map(str::to_string);
+        let awaiting = field(&save, "world.dialogue.awaiting", 3)?.clone();
+        let on_close = field(&save, "world.dialogue.on_close", 3)?.as_str().map(str::to_string);
+
+        // Old prompts and close actions map onto the equivalent graph node.
+        let (node, awaiting) = match (npc.as_deref(), awaiting.as_str(), on_close.as_deref()) {
+            (_, Some("YesNoMayor"), _) => (json!("intro"), json!("Choices")),
+            (_, Some("YesNoDorosht"), _) => (json!("offer"), json!("Choices")),
+            (_, Some("ABNoorWeapon"), _) => (json!("offer"), json!("Choices")),
+            (Some("mayor_sol"), _, Some("battle")) => (json!("reveal"), Value::Null),
+            (Some("mayor_sol"), _, Some("fin")) => (json!("farewell"), Value::Null),
+            (Some(_), _, Some("battle")) => (json!("challenge"), Value::Null),
+            _ => (Value::Null, awaiting),
+        };
+        let d = save["world"]["dialogue"]
+            .as_object_mut()
+            .ok_or_else(|| SaveError::Migration { version: 3, field: "world.dialogue".to_string() })?;
+        d.remove("on_close");
+        d.insert("node".to_string(), node);
+        d.insert("awaiting".to_string(), awaiting);
+
+        // Noor's old three-page prompt is now two pages plus a follow-up node.
+        if d.get("node") == Some(&json!("offer")) && npc.as_deref() == Some("noor") {
+            d.insert("pages".to_string(), Value::Array(
+                d["pages"].as_array().map(|p| p.iter().take(2).cloned().collect()).unwrap_or_default(),
+            ));
+            let idx = d["page_index"].as_u64().unwrap_or(0).min(1);
+            d.insert("page_index".to_string(), json!(idx));
+        }
+    }
+    Ok(save)
+}
+
+/// v5 added quest progress and the journal panel. Quests are picked up from
+/// the flags on the next update; Mah's gate moved onto a quest-stage flag.
+fn migrate_v4_to_v5(mut save: Value) -> Result<Value, SaveError> {
+    let flags = field(&save, "world.flags", 4)?
+        .as_array()
+        .ok_or_else(|| SaveError::Migration { version: 4, field: "world.flags".to_string() })?;
+    let guards_down = ["shab_defeated", "krad_defeated"].iter().all(|f| flags.contains(&json!(f)));
+
+    let world = save["world"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 4, field: "world".to_string() })?;
+    if guards_down
+        && let Some(flags) = world.get_mut("flags").and_then(Value::as_array_mut)
+    {
+        flags.push(json!("weeping_guards_down"));
+    }
+    world.insert("quests".to_string(), json!({}));
+    world.insert("journal_open".to_string(), json!(false));
+    Ok(save)
+}
+
+/// v6 gave the world its own combat RNG. Older saves rolled with the thread
+/// RNG, so they start from the same stream a fresh world with their seed would.
+fn migrate_v5_to_v6(mut save: Value) -> Result<Value, SaveError> {
+    let seed = field(&save, "world.seed", 5)?
+        .as_u64()
+        .ok_or_else(|| SaveError::Migration { version: 5, field: "world.seed".to_string() })?;
+    save["world"]["rng"] = serde_json::to_value(GameRng::for_world(seed))
+        .map_err(|_| SaveError::Migration { version: 5, field: "world.rng".to_string() })?;
+    Ok(save)
+}
+
+/// v7 moved the enemy's stats in `battle` into an `enemy` object shared
+/// with the combat module.
+fn migrate_v6_to_v7(mut save: Value) -> Result<Value, SaveError> {
+    const MOVED: [(&str, &str); 7] = [
+        ("enemy_id", "id"),
+        ("enemy_name", "name"),
+        ("enemy_hp", "hp"),
+        ("enemy_max_hp", "max_hp"),
+        ("enemy_atk", "attack"),
+        ("enemy_def", "defense"),
+        ("enemy_speed", "speed"),
+    ];
+
+    if save["world"]["battle"].is_null() {
+        return Ok(save);
+    }
+    let mut enemy = serde_json::Map::new();
+    for (old, new) in MOVED {
+        enemy.insert(new.to_string(), field(&save, &format!("world.battle.{old}"), 6)?.clone());
+    }
+    let battle = save["world"]["battle"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 6, field: "world.battle".to_string() })?;
+    for (old, _) in MOVED {
+        battle.remove(old);
+    }
+    battle.insert("enemy".to_string(), Value::Object(enemy));
+    Ok(save)
+}
+
+/// v8 added battle status effects to the player and the enemy. An enemy
+/// fought in an older save keeps fighting without inflicting any.
+fn migrate_v7_to_v8(mut save: Value) -> Result<Value, SaveError> {
+    let player = save["world"]["player"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 7, field: "world.player".to_string() })?;
+    player.insert("statuses".to_string(), json!([]));
+
+    if save["world"]["battle"].is_null() {
+        return Ok(save);
+    }
+    let enemy = save["world"]["battle"]["enemy"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 7, field: "world.battle.enemy".to_string() })?;
+    enemy.insert("on_hit".to_string(), json!([]));
+    enemy.insert("statuses".to_string(), json!([]));
+    Ok(save)
+}
+
+/// v9 added skill cooldowns and the skills menu to battles.
+fn migrate_v8_to_v9(mut save: Value) -> Result<Value, SaveError> {
+    if save["world"]["battle"].is_null() {
+        return Ok(save);
+    }
+    let battle = save["world"]["battle"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 8, field: "world.battle".to_string() })?;
+    battle.insert("cooldowns".to_string(), json!({}));
+    battle.insert("skills_open".to_string(), json!(false));
+    battle.insert("skill_cursor".to_string(), json!(0));
+    Ok(save)
+}
+
+/// v10 gave enemies an AI script. One fought in an older save just attacks,
+/// as every enemy used to.
+fn migrate_v9_to_v10(mut save: Value) -> Result<Value, SaveError> {
+    if save["world"]["battle"].is_null() {
+        return Ok(save);
+    }
+    let enemy = save["world"]["battle"]["enemy"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 9, field: "world.battle.enemy".to_string() })?;
+    enemy.insert("ai".to_string(), json!({ "behavior": "aggressive", "phases": [] }));
+    enemy.insert("ai_state".to_string(), json!({ "phase": 0, "turn": 0, "enraged": false, "last_heal": null }));
+    Ok(save)
+}
+
+/// v11 let battles hold a group of enemies. An older battle is a group of
+/// one, already targeted.
+fn migrate_v10_to_v11(mut save: Value) -> Result<Value, SaveError> {
+    if save["world"]["battle"].is_null() {
+        return Ok(save);
+    }
+    let battle = save["world"]["battle"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 10, field: "world.battle".to_string() })?;
+    let enemy = battle
+        .remove("enemy")
+        .ok_or_else(|| SaveError::Migration { version: 10, field: "world.battle.enemy".to_string() })?;
+    battle.insert("enemies".to_string(), json!([enemy]));
+    battle.insert("target".to_string(), json!(0));
+    battle.insert("pending".to_string(), Value::Null);
+    Ok(save)
+}
+
+/// v12 measures buffs in turns instead of real time. Every second that was
+/// left becomes a turn, rounded up.
+fn migrate_v11_to_v12(mut save: Value) -> Result<Value, SaveError> {
+    let path = "world.player.buffs";
+    let buffs = save["world"]["player"]["buffs"]
+        .as_array_mut()
+        .ok_or_else(|| SaveError::Migration { version: 11, field: path.to_string() })?;
+    for buff in buffs.iter_mut().filter_map(Value::as_object_mut) {
+        let ms = buff
+            .remove("expires_at")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| SaveError::Migration { version: 11, field: format!("{path}.expires_at") })?;
+        buff.insert("turns".to_string(), json!(ms.div_ceil(1000)));
+    }
+    Ok(save)
+}
+
+/// v13 replaced the flat stat boost after each win with XP and levels. Older
+/// saves keep the stats they already earned and start at level 1.
+fn migrate_v12_to_v13(mut save: Value) -> Result<Value, SaveError> {
+    let player = save["world"]["player"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 12, field: "world.player".to_string() })?;
+    player.insert("exp".to_string(), json!({ "level": 1, "xp": 0, "points": 0 }));
+    save["world"]["stat_cursor"] = json!(0);
+    Ok(save)
+}
+
+/// v14 added gold. Older saves start broke, with no shop open, and their
+/// unopened chests stay as they were (no gold inside).
+fn migrate_v13_to_v14(mut save: Value) -> Result<Value, SaveError> {
+    let player = save["world"]["player"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 13, field: "world.player".to_string() })?;
+    player.insert("gold".to_string(), json!(0));
+    let levels = save["world"]["levels"]
+        .as_array_mut()
+        .ok_or_else(|| SaveError::Migration { version: 13, field: "world.levels".to_string() })?;
+    for chest in levels.iter_mut().filter_map(|l| l["chests"].as_array_mut()).flatten() {
+        chest["gold"] = json!(0);
+    }
+    save["world"]["shop"] = Value::Null;
+    Ok(save)
+}
+
+/// v15 gave every item the id of the catalogue template it comes from. The
+/// catalogue ids are the item names in snake_case, so older items are tagged
+/// from their names wherever they sit: inventory, chests, or an open chest prompt.
+fn migrate_v14_to_v15(mut save: Value) -> Result<Value, SaveError> {
+    fn tag(item: &mut Value) {
+        let Some(obj) = item.as_object_mut() else { return };
+        let Some(name) = obj.get("name").and_then(Value::as_str) else { return };
+        let id: Vec<&str> = name.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()).collect();
+        obj.insert("id".to_string(), json!(id.join("_").to_ascii_lowercase()));
+    }
+
+    let inventory = save["world"]["player"]["inventory"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 14, field: "world.player.inventory".to_string() })?;
+    for (key, value) in inventory.iter_mut() {
+        match key.as_str() {
+            "sword" | "shield" => tag(value),
+            "consumables" | "backpack" => value.as_array_mut().into_iter().flatten().for_each(tag),
+            _ => {}
+        }
+    }
+    let levels = save["world"]["levels"]
+        .as_array_mut()
+        .ok_or_else(|| SaveError::Migration { version: 14, field: "world.levels".to_string() })?;
+    for chest in levels.iter_mut().filter_map(|l| l["chests"].as_array_mut()).flatten() {
+        tag(&mut chest["item"]);
+        tag(&mut chest["weapon"]);
+    }
+    let prompt = save["world"].get_mut("dialogue")
+        .and_then(|d| d.get_mut("awaiting"))
+        .and_then(|a| a.get_mut("Chest"));
+    if let Some(prompt) = prompt {
+        tag(&mut prompt["item"]);
+        tag(&mut prompt["weapon"]);
+    }
+    Ok(save)
+}
+
+/// v16 replaced the sword and shield fields with a map of worn gear, and the
+/// Weapons tab with the Equipment tab. The old cursor pointed at the sword (0)
+/// or shield (1); those are the main and off hand on the new paper doll.
+fn migrate_v15_to_v16(mut save: Value) -> Result<Value, SaveError> {
+    let path = "world.player.inventory";
+    let inventory = save["world"]["player"]["inventory"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 15, field: path.to_string() })?;
+    let mut equipped = serde_json::Map::new();
+    for (old, new) in [("sword", "main_hand"), ("shield", "off_hand")] {
+        let item = inventory
+            .remove(old)
+            .ok_or_else(|| SaveError::Migration { version: 15, field: format!("{path}.{old}") })?;
+        if !item.is_null() {
+            equipped.insert(new.to_string(), item);
+        }
+    }
+    inventory.insert("equipped".to_string(), Value::Object(equipped));
+    let cursor = inventory
+        .remove("weapon_cursor")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| SaveError::Migration { version: 15, field: format!("{path}.weapon_cursor") })?;
+    inventory.insert("gear_cursor".to_string(), json!(if cursor == 0 { 3 } else { 4 }));
+    if inventory.get("tab").and_then(Value::as_str) == Some("Weapons") {
+        inventory.insert("tab".to_string(), json!("Equipment"));
+    }
+    Ok(save)
+}
+
+/// v17 works max HP out from the stats pipeline instead of storing it. The
+/// player keeps `base_max_hp`: the old total without the bonuses of worn gear.
+fn migrate_v16_to_v17(mut save: Value) -> Result<Value, SaveError> {
+    let path = "world.player";
+    let player = save["world"]["player"]
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Migration { version: 16, field: path.to_string() })?;
+    let max_hp = player
+        .remove("max_hp")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| SaveError::Migration { version: 16, field: format!("{path}.max_hp") })?;
+    let worn: i64 = player.get("inventory")
+        .and_then(|i| i.get("equipped"))
+        .and_then(Value::as_object)
+        .map(|gear| gear.values().filter_map(|e| e.get("hp_bonus").and_then(Value::as_i64)).sum())
+        .unwrap_or(0);
+    player.insert("base_max_hp".to_string(), json!(max_hp - worn));
+    Ok(save)
+}
+
+/// v18 keeps consumables in stacks with a count. Every old item becomes a
+/// stack of one; new pickups of the same item pile onto them.
+fn migrate_v17_to_v18(mut save: Value) -> Result<Value, SaveError> {
+    let path = "world.player.inventory.consumables";
+    let items = save["world"]["player"]["inventory"]
+        .get_mut("consumables")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| SaveError::Migration { version: 17, field: path.to_string() })?;
+    for item in items.iter_mut() {
+        *item = json!({ "item": item.take(), "count": 1 });
+    }
+    Ok(save)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::entity::GearSlot;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/saves/", $name))
+        };
+    }
+
+    const CORPUS: &[(&str, &str)] = &[
+        ("v1_fresh", fixture!("v1_fresh.json")),
+        ("v1_midgame", fixture!("v1_midgame.json")),
+        ("v2_midgame", fixture!("v2_midgame.json")),
+        ("v2_shab_dialogue", fixture!("v2_shab_dialogue.json")),
+        ("v3_midgame", fixture!("v3_midgame.json")),
+        ("v3_noor_offer", fixture!("v3_noor_offer.json")),
+        ("v4_midgame", fixture!("v4_midgame.json")),
+        ("v5_midgame", fixture!("v5_midgame.json")),
+        ("v6_midgame", fixture!("v6_midgame.json")),
+        ("v6_battle", fixture!("v6_battle.json")),
+        ("v7_battle", fixture!("v7_battle.json")),
+        ("v8_battle", fixture!("v8_battle.json")),
+        ("v9_battle", fixture!("v9_battle.json")),
+        ("v10_battle", fixture!("v10_battle.json")),
+        ("v11_battle", fixture!("v11_battle.json")),
+        ("v12_midgame", fixture!("v12_midgame.json")),
+        ("v13_midgame", fixture!("v13_midgame.json")),
+        ("v14_midgame", fixture!("v14_midgame.json")),
+        ("v15_midgame", fixture!("v15_midgame.json")),
+        ("v16_midgame", fixture!("v16_midgame.json")),
+        ("v17_midgame", fixture!("v17_midgame.json")),
+        ("v18_midgame", fixture!("v18_midgame.json")),
+    ];
+
+    #[test]
+    fn every_fixture_loads_and_lists() {
+        for (name, json) in CORPUS {
+            let world = load_world_from_str(json).unwrap_or_else(|e| panic!("{name}: {e}"));
+            assert_eq!(world.seed, 42, "{name}");
+            read_header(json).unwrap_or_else(|e| panic!("{name} header: {e}"));
+        }
+    }
+
+    #[test]
+    fn v1_midgame_keeps_progress() {
+        let mut world = load_world_from_str(fixture!("v1_midgame.json")).unwrap();
+        assert_eq!(world.player.hp, 24);
+        assert_eq!(world.player.inventory.gear(GearSlot::MainHand).unwrap().id.as_str(), "basic_sword");
+        assert_eq!(world.player.inventory.consumables.len(), 1);
+        assert!(world.levels[0].chests[0].opened);
+        assert!(world.flag("mayor_done") && world.flag("noor_done"));
+        assert_eq!(world.player.buffs[0].turns, 20);
+
+        // Quests catch up with the old flags on the first update.
+        world.apply_action(crate::engine::action::Action::None);
+        assert_eq!(world.progress_summary(), "The Weeping 0/4");
+        let (_, weeping) = world.journal()[0];
+        assert_eq!(weeping.objectives_done, vec![true, false]);
+
+        let meta = read_header(fixture!("v1_midgame.json")).unwrap();
+        assert_eq!((meta.hp, meta.max_hp, meta.room), (24, 30, 0));
+        assert_eq!(meta.progress, "Story 2/9");
+    }
+
+    #[test]
+    fn v2_dialogue_keeps_its_battle_trigger() {
+        let world = load_world_from_str(fixture!("v2_shab_dialogue.json")).unwrap();
+        let d = world.dialogue.as_ref().unwrap();
+        assert_eq!(d.npc.as_ref().map(|id| id.as_str()), Some("shab"));
+        assert_eq!(d.node.as_deref(), Some("challenge"));
+        assert!(world.npcs.iter().any(|n| n.id.as_str() == "mayor_sol"));
+    }
+
+    #[test]
+    fn v3_prompt_becomes_graph_node() {
+        let world = load_world_from_str(fixture!("v3_noor_offer.json")).unwrap();
+        let d = world.dialogue.as_ref().unwrap();
+        assert_eq!(d.node.as_deref(), Some("offer"));
+        assert_eq!(d.pages.len(), 2);
+        assert!(matches!(d.awaiting, Some(crate::engine::world::AwaitingChoice::Choices)));
+        assert!(world.flag("mayor_done"));
+        assert!(!world.flag("noor_done"));
+    }
+
+    #[test]
+    fn v6_battle_keeps_the_enemy() {
+        let world = load_world_from_str(fixture!("v6_battle.json")).unwrap();
+        let enemy = &world.battle.as_ref().unwrap().enemies[0];
+        assert_eq!(enemy.id.as_str(), "krad");
+        assert_eq!((enemy.hp, enemy.max_hp, enemy.attack, enemy.defense), (8, 20, 6, 4));
+        assert!(enemy.on_hit.is_empty() && enemy.statuses.is_empty());
+    }
+
+    #[test]
+    fn missing_field_names_field_and_version() {
+        match load_world_from_str(fixture!("v1_missing_hp.json")) {
+            Err(SaveError::Migration { version, field }) => {
+                assert_eq!(version, 1);
+                assert_eq!(field, "world.player.hp");
+            }
+            other => panic!("expected migration error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn bad_field_after_migration_is_a_schema_error() {
+        let mut value: Value = serde_json::from_str(fixture!("v2_midgame.json")).unwrap();
+        value["world"]["player"]["inventory"]["tab"] = json!("Spells");
+        match load_world_from_str(&value.to_string()) {
+            Err(SaveError::Schema { version, field, .. }) => {
+                assert_eq!(version, 2);
+                assert_eq!(field, "world.player.inventory.tab");
+            }
+            other => panic!("expected schema error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn empty_stacks_are_a_schema_error() {
+        let mut value: Value = serde_json::from_str(fixture!("v18_midgame.json")).unwrap();
+        value["world"]["player"]["inventory"]["consumables"][0]["count"] = json!(0);
+        match load_world_from_str(&value.to_string()) {
+            Err(SaveError::Schema { version, field, .. }) => {
+                assert_eq!(version, 18);
+                assert!(field.starts_with("world.player.inventory.consumables"), "{field}");
+            }
+            other => panic!("expected schema error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn future_version_is_rejected() {
+        let json = json!({ "version": SAVE_VERSION + 1, "world": {} }).to_string();
+        assert!(matches!(load_world_from_str(&json), Err(SaveError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    fn round_trip_is_identical() {
+        let world = World::new(7, 40, 24);
+        let dir = std::env::temp_dir().join(format!("sunny-days-save-{}", std::process::id()));
+        let path = dir.join("slot1.json");
+
+        save_world(&world, &path).unwrap();
+        let loaded = load_world(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            serde_json::to_value(&world).unwrap(),
+            serde_json::to_value(&loaded).unwrap(),
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use crate::engine::entity::{InvSelection, Player};
+use crate::engine::item::ItemRegistry;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShopMode {
+    Buy,
+    Sell, // works on the inventory tabs and their cursors
+}
+
+/// An open merchant menu.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShopSession {
+    pub mode: ShopMode,
+    pub cursor: usize, // into the registry's `shop` list while buying
+}
+
+/// Buys the stock item at `index`. Returns the log line either way.
+pub fn buy(player: &mut Player, items: &ItemRegistry, index: usize) -> Result<String, String> {
+    let Some(id) = items.shop().get(index) else { return Err("Nothing to buy.".to_string()) };
+    let name = items.name(id).unwrap_or(id.as_str());
+    let price = items.price(id).unwrap_or(0);
+    if player.gold < price {
+        return Err(format!("{} costs {} gold; you have {}.", name, price, player.gold));
+    }
+    if let Some(c) = items.new_consumable(id) {
+        if !player.inventory.add_consumable(c) {
+            return Err("Slots full.".to_string());
+        }
+    } else if let Some(e) = items.new_equipment(id) {
+        player.inventory.backpack.push(e);
+    }
+    player.gold -= price;
+    Ok(format!("Bought {} for {} gold.", name, price))
+}
+
+/// Sells whatever the inventory cursor is on, one off a stack at a time.
+/// Equipped gear has to come off first.
+pub fn sell(player: &mut Player, items: &ItemRegistry) -> Result<String, String> {
+    let inv = &mut player.inventory;
+    let (id, name, index) = match inv.selection() {
+        InvSelection::Consumable(i) => (&inv.consumables[i].item.id, inv.consumables[i].item.name.clone(), i),
+        InvSelection::BackpackItem(i) => (&inv.backpack[i].id, inv.backpack[i].name.clone(), i),
+        InvSelection::Gear(_) => return Err("Unequip it first.".to_string()),
+        InvSelection::None => return Err("Nothing to sell.".to_string()),
+    };
+    let Some(price) = items.sell_price(id) else { return Err(format!("The merchant won't take the {}.", name)) };
+
+    match inv.selection() {
+        InvSelection::Consumable(_) => {
+            inv.take_consumable(index);
+        }
+        _ => {
+            inv.backpack.remove(index);
+            inv.backpack_cursor = inv.backpack_cursor.min(inv.backpack.len().saturating_sub(1));
+        }
+    }
+    player.gold += price;
+    Ok(format!("Sold {} for {} gold.", name, price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::entity::{GearSlot, InvTab, Stack};
+    use crate::engine::item::ItemId;
+
+    #[test]
+    fn buying_needs_the_gold_and_selling_pays_half() {
+        let items = ItemRegistry::builtin();
+        let mut player = Player::new(0, 0);
+        let salve = items.shop().iter().position(|id| id.as_str() == "healing_salve").unwrap();
+
+        player.gold = 10;
+        assert!(buy(&mut player, &items, salve).is_err());
+        player.gold = 20;
+        assert_eq!(buy(&mut player, &items, salve).unwrap(), "Bought Healing salve for 15 gold.");
+        assert_eq!(player.gold, 5);
+
+        player.inventory.tab = InvTab::Consumables;
+        assert_eq!(sell(&mut player, &items).unwrap(), "Sold Healing salve for 7 gold.");
+        assert_eq!(player.gold, 12);
+        assert!(player.inventory.consumables.is_empty());
+        assert!(sell(&mut player, &items).is_err());
+    }
+
+    #[test]
+    fn gear_goes_to_the_backpack_and_full_slots_refuse() {
+        let items = ItemRegistry::builtin();
+        let mut player = Player::new(0, 0);
+        let stock = |id: &str| items.shop().iter().position(|s| s.as_str() == id).unwrap();
+        player.gold = 100;
+
+        assert_eq!(buy(&mut player, &items, stock("iron_helm")).unwrap(), "Bought Iron Helm for 15 gold.");
+        buy(&mut player, &items, stock("copper_ring")).unwrap();
+        assert_eq!(player.gold, 73);
+        assert_eq!(player.inventory.backpack.len(), 2);
+
+        player.inventory.tab = InvTab::Backpack;
+        player.inventory.backpack_cursor = 1;
+        assert_eq!(sell(&mut player, &items).unwrap(), "Sold Copper Ring for 6 gold.");
+        assert_eq!((player.inventory.backpack.len(), player.inventory.backpack_cursor), (1, 0));
+        assert_eq!(player.gold, 79);
+
+        let helm = player.inventory.backpack.remove(0);
+        player.equip(helm).unwrap();
+        player.inventory.tab = InvTab::Equipment;
+        player.inventory.gear_cursor = GearSlot::ALL.iter().position(|&s| s == GearSlot::Helmet).unwrap();
+        assert_eq!(sell(&mut player, &items).unwrap_err(), "Unequip it first.");
+        assert!(player.inventory.gear(GearSlot::Helmet).is_some());
+
+        let jerky = items.new_consumable(&ItemId::new("sunny_jerky")).unwrap();
+        player.inventory.consumables = vec![Stack { item: jerky, count: 5 }; player.inventory.capacity()];
+        assert_eq!(buy(&mut player, &items, stock("sunny_jerky")).unwrap_err(), "Slots full.");
+        assert_eq!(player.gold, 79);
+    }
+}
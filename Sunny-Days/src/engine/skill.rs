@@ -0,0 +1,90 @@
+use crate::engine::data::{self, DataFile};
+use crate::engine::dialogue::Condition;
+use crate::engine::status::StatusSpec;
+
+use serde::Deserialize;
+
+use std::{error::Error, sync::OnceLock};
+
+const SKILL_DATA: &str = "assets/data/skills.json";
+const BUILTIN_SKILLS: &str = include_str!("../../assets/data/skills.json");
+
+/// What a skill does when it's the player's turn to act.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkillEffect {
+    /// An attack with its damage multiplied by `power`.
+    Strike { power: f32 },
+    /// The next enemy hit within `turns` battle turns is deflected.
+    Guard { turns: u32 },
+    /// Puts a status on the enemy.
+    Inflict(StatusSpec),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Battle turns to wait before it can be used again.
+    pub cooldown: u32,
+    pub effect: SkillEffect,
+    /// Learned once all of these hold. Empty means the skill only comes from
+    /// equipment that lists it in `skills`.
+    #[serde(default)]
+    pub unlock_when: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillRegistry {
+    skills: Vec<SkillDef>,
+}
+
+impl SkillRegistry {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let reg: SkillRegistry = serde_json::from_str(json)?;
+        reg.validate()?;
+        Ok(reg)
+    }
+
+    pub fn builtin() -> Self {
+        data::parse_builtin(SKILL_DATA, BUILTIN_SKILLS)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, s) in self.skills.iter().enumerate() {
+            if self.skills[..i].iter().any(|o| o.id == s.id) {
+                return Err(format!("duplicate skill id `{}`", s.id));
+            }
+            if let SkillEffect::Strike { power } = s.effect
+                && power <= 0.0
+            {
+                return Err(format!("skill `{}` has a strike power of {power}", s.id));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn all(&self) -> &[SkillDef] {
+        &self.skills
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SkillDef> {
+        self.skills.iter().find(|s| s.id == id)
+    }
+}
+
+impl DataFile for SkillRegistry {
+    const WHAT: &'static str = "Skill data";
+
+    fn parse(json: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_json(json)
+    }
+}
+
+static REGISTRY: OnceLock<SkillRegistry> = OnceLock::new();
+
+/// Skill definitions, read from `assets/data/skills.json` the first time they're needed.
+pub fn registry() -> &'static SkillRegistry {
+    REGISTRY.get_or_init(|| data::load_registry(SKILL_DATA, BUILTIN_SKILLS))
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stat {
+    MaxHp,
+    Attack,
+    Defense,
+    Speed,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 4] = [Stat::MaxHp, Stat::Attack, Stat::Defense, Stat::Speed];
+}
+
+/// One change to a stat from gear, a buff or a status.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Modifier {
+    Add(Stat, i32),
+    Mul(Stat, f32), // 1.1 is +10%
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub max_hp: i32,
+    pub attack: i32,
+    pub defense: i32,
+    pub speed: i32,
+}
+
+impl Stats {
+    pub fn get(&self, stat: Stat) -> i32 {
+        match stat {
+            Stat::MaxHp => self.max_hp,
+            Stat::Attack => self.attack,
+            Stat::Defense => self.defense,
+            Stat::Speed => self.speed,
+        }
+    }
+
+    fn get_mut(&mut self, stat: Stat) -> &mut i32 {
+        match stat {
+            Stat::MaxHp => &mut self.max_hp,
+            Stat::Attack => &mut self.attack,
+            Stat::Defense => &mut self.defense,
+            Stat::Speed => &mut self.speed,
+        }
+    }
+
+    /// Runs these base stats through `mods`: every `Add` first, then every
+    /// `Mul` on the total, rounded to the nearest point. The order the
+    /// modifiers come in doesn't matter.
+    pub fn apply(self, mods: impl IntoIterator<Item = Modifier>) -> Stats {
+        let mut added = self;
+        let mut factor = [1.0f32; 4];
+        for m in mods {
+            match m {
+                Modifier::Add(stat, n) => *added.get_mut(stat) += n,
+                Modifier::Mul(stat, f) => factor[stat as usize] *= f,
+            }
+        }
+        let mut out = added;
+        for stat in Stat::ALL {
+            *out.get_mut(stat) = (added.get(stat) as f32 * factor[stat as usize]).round() as i32;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::entity::{GearSlot, Player};
+    use crate::engine::item::{ItemId, ItemRegistry};
+    use crate::engine::status::{self, StatusKind};
+
+    #[test]
+    fn adds_land_before_multipliers_in_any_order() {
+        let base = Stats { max_hp: 30, attack: 10, defense: 8, speed: 5 };
+        let mods = [Modifier::Mul(Stat::MaxHp, 1.5), Modifier::Add(Stat::MaxHp, 10), Modifier::Add(Stat::Speed, -2)];
+        let out = base.apply(mods);
+        assert_eq!(out, Stats { max_hp: 60, attack: 10, defense: 8, speed: 3 });
+        assert_eq!(base.apply(mods.into_iter().rev()), out);
+        assert_eq!(base.apply([]), base);
+    }
+
+    #[test]
+    fn equip_cycles_never_drift() {
+        let items = ItemRegistry::builtin();
+        let mut p = Player::new(0, 0);
+        p.add_temp_buff(2, 1, 0, 3);
+        status::apply(&mut p.statuses, StatusKind::Tears, 2);
+        let start = p.stats();
+
+        let ids: Vec<ItemId> = items.chest_gear().iter().cloned()
+            .chain(["willow_axe", "shield_of_healing"].map(ItemId::new))
+            .collect();
+        for _ in 0..3 {
+            for id in &ids {
+                p.equip(items.new_equipment(id).unwrap()).unwrap();
+                assert!(p.hp <= p.max_hp());
+            }
+            while let Some(&slot) = p.inventory.equipped.keys().next() {
+                p.unequip(slot);
+                assert!(p.hp <= p.max_hp());
+            }
+            p.inventory.backpack.clear();
+            assert_eq!(p.stats(), start);
+        }
+
+        p.equip(items.new_equipment(&ItemId::new("sun_amulet")).unwrap()).unwrap();
+        assert_eq!(p.max_hp(), ((start.max_hp + 3) as f32 * 1.1).round() as i32);
+        p.hp = p.max_hp();
+        p.unequip(GearSlot::Amulet);
+        assert_eq!((p.hp, p.max_hp()), (start.max_hp, start.max_hp));
+    }
+
+    #[test]
+    fn previews_match_equipping() {
+        let items = ItemRegistry::builtin();
+        let mut p = Player::new(0, 0);
+        p.equip(items.new_equipment(&ItemId::new("basic_shield")).unwrap()).unwrap();
+        let axe = items.new_equipment(&ItemId::new("willow_axe")).unwrap();
+        let before = p.stats();
+
+        let (preview, removed) = p.preview_equip(&axe);
+        assert_eq!(removed, vec!["Basic Shield".to_string()]);
+        assert_eq!(p.stats(), before);
+        p.equip(axe).unwrap();
+        assert_eq!(p.stats(), preview);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::engine::stats::{Modifier, Stat};
+
+use serde::{Deserialize, Serialize};
+
+/// Defense lost per stack of tears.
+pub const TEARS_DEF_PER_STACK: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusKind {
+    Poison,
+    Stun,
+    Bleed,
+    Regen,
+    Tears,
+    Guard,
+}
+
+impl StatusKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            StatusKind::Poison => "Poison",
+            StatusKind::Stun => "Stun",
+            StatusKind::Bleed => "Bleed",
+            StatusKind::Regen => "Regen",
+            StatusKind::Tears => "Tears",
+            StatusKind::Guard => "Guard",
+        }
+    }
+
+    /// "You are ___!" when the status lands.
+    pub fn afflicted(self) -> &'static str {
+        match self {
+            StatusKind::Poison => "poisoned",
+            StatusKind::Stun => "stunned",
+            StatusKind::Bleed => "bleeding",
+            StatusKind::Regen => "regenerating",
+            StatusKind::Tears => "in tears",
+            StatusKind::Guard => "on guard",
+        }
+    }
+
+    pub fn max_stacks(self) -> u32 {
+        match self {
+            StatusKind::Poison | StatusKind::Bleed | StatusKind::Tears => 3,
+            StatusKind::Stun | StatusKind::Regen | StatusKind::Guard => 1,
+        }
+    }
+
+    /// HP change per stack at the end of every battle turn.
+    pub fn hp_per_turn(self) -> i32 {
+        match self {
+            StatusKind::Poison => -2,
+            StatusKind::Bleed => -3,
+            StatusKind::Regen => 3,
+            StatusKind::Stun | StatusKind::Tears | StatusKind::Guard => 0,
+        }
+    }
+}
+
+fn always() -> f32 {
+    1.0
+}
+
+/// A status as handed out by an enemy's hits, a weapon's hits or a consumable.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatusSpec {
+    pub kind: StatusKind,
+    pub turns: u32,
+    #[serde(default = "always")]
+    pub chance: f32, // per landed hit; consumables always apply
+}
+
+/// A status on a combatant. `turns` counts battle turns left; for stun it is
+/// the number of actions still to skip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub turns: u32,
+    pub stacks: u32,
+}
+
+/// Adds a status. One already there gains a stack (up to its cap) and keeps
+/// the longer of the two durations.
+pub fn apply(list: &mut Vec<StatusEffect>, kind: StatusKind, turns: u32) {
+    if turns == 0 {
+        return;
+    }
+    match list.iter_mut().find(|s| s.kind == kind) {
+        Some(s) => {
+            s.stacks = (s.stacks + 1).min(kind.max_stacks());
+            s.turns = s.turns.max(turns);
+        }
+        None => list.push(StatusEffect { kind, turns, stacks: 1 }),
+    }
+}
+
+pub fn has(list: &[StatusEffect], kind: StatusKind) -> bool {
+    list.iter().any(|s| s.kind == kind)
+}
+
+/// Uses up one turn of stun; true if there was one to use.
+pub fn consume_stun(list: &mut Vec<StatusEffect>) -> bool {
+    let Some(i) = list.iter().position(|s| s.kind == StatusKind::Stun) else { return false };
+    list[i].turns -= 1;
+    if list[i].turns == 0 {
+        list.remove(i);
+    }
+    true
+}
+
+/// Drops a guard that is about to block a hit; true if there was one.
+pub fn consume_guard(list: &mut Vec<StatusEffect>) -> bool {
+    let Some(i) = list.iter().position(|s| s.kind == StatusKind::Guard) else { return false };
+    list.remove(i);
+    true
+}
+
+pub fn defense_mod(list: &[StatusEffect]) -> i32 {
+    list.iter()
+        .filter(|s| s.kind == StatusKind::Tears)
+        .map(|s| -TEARS_DEF_PER_STACK * s.stacks as i32)
+        .sum()
+}
+
+/// What these statuses do to stats, for the player's stat pipeline.
+pub fn modifiers(list: &[StatusEffect]) -> [Modifier; 1] {
+    [Modifier::Add(Stat::Defense, defense_mod(list))]
+}
+
+/// Counts every status but stun down by one turn and returns the ones that ran out.
+pub fn count_down(list: &mut Vec<StatusEffect>) -> Vec<StatusKind> {
+    let mut ended = Vec::new();
+    list.retain_mut(|s| {
+        if s.kind == StatusKind::Stun {
+            return true;
+        }
+        s.turns -= 1;
+        if s.turns == 0 {
+            ended.push(s.kind);
+        }
+        s.turns > 0
+    });
+    ended
+}
+
+/// Short form for the battle panel, e.g. "Poison x2 (3), Stun (1)".
+pub fn describe(list: &[StatusEffect]) -> String {
+    list.iter()
+        .map(|s| {
+            if s.stacks > 1 {
+                format!("{} x{} ({})", s.kind.label(), s.stacks, s.turns)
+            } else {
+                format!("{} ({})", s.kind.label(), s.turns)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacks_are_capped_and_keep_the_longer_duration() {
+        let mut list = Vec::new();
+        for turns in [2, 5, 1, 3] {
+            apply(&mut list, StatusKind::Poison, turns);
+        }
+        assert_eq!(list, vec![StatusEffect { kind: StatusKind::Poison, turns: 5, stacks: 3 }]);
+
+        apply(&mut list, StatusKind::Regen, 2);
+        apply(&mut list, StatusKind::Regen, 2);
+        assert_eq!(list[1].stacks, 1);
+    }
+
+    #[test]
+    fn count_down_leaves_stun_alone() {
+        let mut list = Vec::new();
+        apply(&mut list, StatusKind::Bleed, 1);
+        apply(&mut list, StatusKind::Stun, 1);
+        assert_eq!(count_down(&mut list), vec![StatusKind::Bleed]);
+        assert!(consume_stun(&mut list));
+        assert!(list.is_empty());
+        assert!(!consume_stun(&mut list));
+    }
+
+    #[test]
+    fn tears_lower_defense_per_stack() {
+        let mut list = Vec::new();
+        apply(&mut list, StatusKind::Tears, 3);
+        apply(&mut list, StatusKind::Tears, 3);
+        assert_eq!(defense_mod(&list), -2 * TEARS_DEF_PER_STACK);
+    }
+}
@@ -1,32 +1,55 @@
 use crate::engine::action::Action;
+use crate::engine::combat::{self, CombatEvent, Combatant, Enemy, Outcome, TurnChoice, TurnRules};
+use crate::engine::dialogue::{Condition, Effect};
+use crate::engine::door::{self, DoorRule};
+use crate::engine::npc::{self, NpcId, Placement};
+use crate::engine::quest::{self, Objective, QuestDef, QuestProgress};
+use crate::engine::rarity;
+use crate::engine::item::{self, ItemId};
+use crate::engine::rng::GameRng;
+use crate::engine::shop::{self, ShopMode, ShopSession};
+use crate::engine::skill::{self, SkillDef, SkillEffect};
+use crate::engine::status;
+use crate::engine::xp::StatChoice;
 use crate::engine::entity::{
-    Equipment, Player, InvSelection, InvTab, Consumable, EquipSlot as Slot,
+    Equipment, Player, InvSelection, InvTab, Consumable,
 };
 use crate::map::{generator::generate_rooms_and_corridors, tile::Tile, Map};
 
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
 
-use std::collections::VecDeque;
-use std::time::Duration;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-#[derive(Clone)]
+/// Map size used by the game itself.
+pub const MAP_WIDTH: usize = 80;
+pub const MAP_HEIGHT: usize = 45;
+
+/// How long a consumable's ATK/DEF boost lasts, in steps or battle turns.
+pub const BUFF_TURNS: u32 = 30;
+
+/// Single items the chest swap list shows at once, lettered A-D.
+const SWAP_PAGE: usize = 4;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Chest {
     pub x: i32,
     pub y: i32,
     pub item: Option<Consumable>,
     pub weapon: Option<Equipment>,
+    pub gold: u32, // picked up as soon as the chest opens
     pub opened: bool,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Level {
     pub map: Map,
     pub door: (i32, i32),
     pub chests: Vec<Chest>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameState {
     Title,
     Intro,
@@ -36,25 +59,7 @@ pub enum GameState {
     Fin,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum NpcId {
-    MayorSol,
-    Noor,
-    Lamp,
-    Random1,
-    Random2,
-    Random3,
-    Weeping1,
-    Weeping2,
-    Weeping3,
-    Weeping4,
-    Shab,
-    Krad,
-    Mah,
-    Dorosht,
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Npc {
     pub id: NpcId,
     pub name: String,
@@ -64,43 +69,56 @@ pub struct Npc {
     pub symbol: char,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AwaitingChoice {
-    YesNoMayor,
-    YesNoDorosht,
-    ABNoorWeapon,
+    Choices, // the current dialogue node's choices
     Chest {
         room: usize,
         x: i32,
         y: i32,
         item: Option<Consumable>,
-        weapon: Option<Equipment>,
+        weapon: Option<Box<Equipment>>,
+    },
+    /// A chest consumable with nowhere to go, waiting for a single item to
+    /// trade places with. `page` is which four of them are on screen.
+    Swap {
+        item: Consumable,
+        #[serde(default)]
+        page: usize,
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DialogueSession {
-    pub npc: NpcId,
+    pub npc: Option<NpcId>,   // None for chests and post-battle lines
+    pub node: Option<String>, // node in the NPC's dialogue graph
     pub title: String,
     pub pages: Vec<String>,
     pub page_index: usize,
     pub awaiting: Option<AwaitingChoice>,
 }
 
-#[derive(Debug, Clone)]
+/// An attack waiting for the player to pick which enemy it goes at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingAttack {
+    Fight,
+    Skill(String), // skill id
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BattleSession {
-    pub enemy_id: NpcId,
-    pub enemy_name: String,
-    pub enemy_hp: i32,
-    pub enemy_max_hp: i32,
-    pub enemy_atk: i32,
-    pub enemy_def: i32,
-    pub enemy_speed: i32,
-    
+    pub enemies: Vec<Enemy>, // the NPC that was challenged comes first
+    pub target: usize,
+    pub pending: Option<PendingAttack>,
     pub penalty_mode: bool,
     pub player_initiated: bool,
+    pub cooldowns: BTreeMap<String, u32>, // skill id -> battle turns until it's ready
+    pub skills_open: bool,
+    pub skill_cursor: usize,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct World {
     pub levels: Vec<Level>,
     pub current: usize,
@@ -108,33 +126,37 @@ pub struct World {
 
     pub logs: VecDeque<String>,
     pub seed: u64,
+    // Combat rolls; advances as the run goes, unlike `seed`.
+    rng: GameRng,
 
     pub inventory_open: bool,
     pub stats_open: bool,
+    pub stat_cursor: usize, // into `StatChoice::ALL`, while there are points to spend
+    pub journal_open: bool,
     pub state: GameState,
 
     intro_lines: Vec<String>,
 
     pub npcs: Vec<Npc>,
     
-    mayor_done: bool,
-    noor_done: bool,
-    lamp_done: bool,
-    
-    dorosht_accepted: bool,
-    dorosht_completed: bool,
-    
-    shab_defeated: bool,
-    krad_defeated: bool,
-    mah_defeated: bool,
-    mayor_defeated: bool,
+    // Named story flags, set by dialogue effects and battle wins.
+    flags: BTreeSet<String>,
+    // Started quests by id; see `assets/data/quests.json`.
+    quests: BTreeMap<String, QuestProgress>,
 
     pub dialogue: Option<DialogueSession>,
     pub battle: Option<BattleSession>,
+    pub shop: Option<ShopSession>,
+
+    // Set on room changes and battle wins; the game loop writes the autosave slot.
+    #[serde(skip)]
+    pub autosave_requested: bool,
 }
 
 impl World {
     const NPC_MIN_SEP: i32 = 5;
+    /// Odds that a random chest holds a rolled piece of gear instead of a consumable.
+    const GEAR_CHANCE: f64 = 0.25;
 
     pub fn new(seed: u64, width: usize, height: usize) -> Self {
         let (level0, spawn0) = Self::make_level(seed, 0, width, height);
@@ -148,6 +170,7 @@ impl World {
         logs.push_back("Press I to open inventory.".to_string());
         logs.push_back("Press T to toggle inventory tabs.".to_string());
         logs.push_back("Press Q to open stats.".to_string());
+        logs.push_back("Press J to open the quest journal.".to_string());
 
         let intro_lines = vec![
             "Welcome to the Sunny Day, where everything was once bright".to_string(),
@@ -164,26 +187,25 @@ impl World {
 
             logs,
             seed,
+            rng: GameRng::for_world(seed),
 
             inventory_open: false,
             stats_open: false,
+            stat_cursor: 0,
+            journal_open: false,
             state: GameState::Title,
 
             intro_lines,
 
             npcs: Vec::new(),
-            mayor_done: false,
-            noor_done: false,
-            lamp_done: false,
-            dorosht_accepted: false,
-            dorosht_completed: false,
-            shab_defeated: false,
-            krad_defeated: false,
-            mah_defeated: false,
-            mayor_defeated: false,
+            flags: BTreeSet::new(),
+            quests: BTreeMap::new(),
 
             dialogue: None,
             battle: None,
+            shop: None,
+
+            autosave_requested: false,
         };
 
         world.spawn_npcs(spawn0);
@@ -221,70 +243,34 @@ impl World {
     }
 
     fn spawn_npcs(&mut self, spawn0: (i32, i32)) {
-        // --- ROOM 1 ---
-        let mut mx = spawn0.0 + 5;
-        let mut my = spawn0.1;
-
-        if !self.is_floor(0, mx, my) {
-            let candidates = [(mx, my), (mx, my + 1), (mx, my - 1), (mx + 1, my), (mx - 1, my)];
-            for (cx, cy) in candidates {
-                if self.is_floor(0, cx, cy) {
-                    mx = cx;
-                    my = cy;
-                    break;
+        let mut taken: Vec<Vec<(i32, i32)>> = self.levels.iter()
+            .map(|l| {
+                let mut t = vec![l.door];
+                for ch in &l.chests { t.push((ch.x, ch.y)); }
+                t
+            })
+            .collect();
+        taken[0].push(spawn0);
+
+        for def in npc::registry().all() {
+            let room = def.room;
+            let (x, y) = match def.placement {
+                Placement::NearSpawn { dx, dy } => {
+                    let (mut x, mut y) = (spawn0.0 + dx, spawn0.1 + dy);
+                    if !self.is_floor(room, x, y) {
+                        let candidates = [(x, y + 1), (x, y - 1), (x + 1, y), (x - 1, y)];
+                        if let Some(&(cx, cy)) = candidates.iter().find(|&&(cx, cy)| self.is_floor(room, cx, cy)) {
+                            x = cx;
+                            y = cy;
+                        }
+                    }
+                    (x, y)
                 }
-            }
-        }
-
-        self.npcs.push(Npc {
-            id: NpcId::MayorSol,
-            name: "Mayor Sol".to_string(),
-            room: 0,
-            x: mx,
-            y: my,
-            symbol: 'M',
-        });
-
-        let mut taken_r0: Vec<(i32, i32)> = vec![
-            (spawn0.0, spawn0.1),
-            (mx, my),
-            self.levels[0].door,
-        ];
-        for ch in &self.levels[0].chests { taken_r0.push((ch.x, ch.y)); }
-
-        for (id, sym, name) in [(NpcId::Noor, 'N', "Noor"), (NpcId::Lamp, 'L', "Lamp"), (NpcId::Dorosht, 'D', "Dorosht")] {
-            let (x, y) = self.random_floor_spaced(0, &taken_r0, Self::NPC_MIN_SEP);
-            taken_r0.push((x, y));
-            self.npcs.push(Npc { id, name: name.to_string(), room: 0, x, y, symbol: sym });
-        }
-
-        for id in [NpcId::Random1, NpcId::Random2, NpcId::Random3] {
-            let (vx, vy) = self.random_floor_spaced(0, &taken_r0, Self::NPC_MIN_SEP);
-            taken_r0.push((vx, vy));
-            self.npcs.push(Npc { id, name: "Villager".to_string(), room: 0, x: vx, y: vy, symbol: '●' });
-        }
-
-        // --- ROOM 2 ---
-        let mut taken_r1: Vec<(i32, i32)> = vec![self.levels[1].door];
-        for ch in &self.levels[1].chests { taken_r1.push((ch.x, ch.y)); }
-
-        for id in [NpcId::Weeping1, NpcId::Weeping2, NpcId::Weeping3, NpcId::Weeping4] {
-            let (wx, wy) = self.random_floor_spaced(1, &taken_r1, Self::NPC_MIN_SEP);
-            taken_r1.push((wx, wy));
-            self.npcs.push(Npc { id, name: "Weeping Villager".to_string(), room: 1, x: wx, y: wy, symbol: '●' });
+                Placement::Spaced => self.random_floor_spaced(room, &taken[room], Self::NPC_MIN_SEP),
+            };
+            taken[room].push((x, y));
+            self.npcs.push(Npc { id: def.id.clone(), name: def.name.clone(), room, x, y, symbol: def.symbol });
         }
-
-        let (sx, sy) = self.random_floor_spaced(1, &taken_r1, Self::NPC_MIN_SEP);
-        taken_r1.push((sx, sy));
-        self.npcs.push(Npc { id: NpcId::Shab, name: "Shab".to_string(), room: 1, x: sx, y: sy, symbol: 'S' });
-
-        let (kx, ky) = self.random_floor_spaced(1, &taken_r1, Self::NPC_MIN_SEP);
-        taken_r1.push((kx, ky));
-        self.npcs.push(Npc { id: NpcId::Krad, name: "Krad".to_string(), room: 1, x: kx, y: ky, symbol: 'K' });
-
-        let (bx, by) = self.random_floor_spaced(1, &taken_r1, Self::NPC_MIN_SEP);
-        taken_r1.push((bx, by));
-        self.npcs.push(Npc { id: NpcId::Mah, name: "Mah".to_string(), room: 1, x: bx, y: by, symbol: 'M' });
     }
 
     fn is_floor(&self, room: usize, x: i32, y: i32) -> bool {
@@ -309,6 +295,32 @@ impl World {
     }
 
     pub fn intro_lines(&self) -> &[String] { &self.intro_lines }
+
+    /// Short story-progress label for save slot listings.
+    pub fn progress_summary(&self) -> String {
+        let all = quest::registry().all();
+        let done = self.quests.values().filter(|p| p.completed).count();
+        match self.journal().into_iter().find(|(_, p)| !p.completed) {
+            Some((def, p)) => format!("{} {}/{}", def.title, p.stage, def.stages.len()),
+            None if done > 0 => format!("Quests {}/{}", done, all.len()),
+            None => "Just arrived".to_string(),
+        }
+    }
+
+    /// Started quests in data-file order, for the journal.
+    pub fn journal(&self) -> Vec<(&'static QuestDef, &QuestProgress)> {
+        quest::registry().all().iter()
+            .filter_map(|def| self.quests.get(&def.id).map(|p| (def, p)))
+            .collect()
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    pub fn set_flag(&mut self, name: impl Into<String>) {
+        self.flags.insert(name.into());
+    }
     fn current_level(&self) -> &Level { &self.levels[self.current] }
     fn current_level_mut(&mut self) -> &mut Level { &mut self.levels[self.current] }
     pub fn current_map(&self) -> &Map { &self.current_level().map }
@@ -354,12 +366,10 @@ impl World {
     }
 
     fn random_consumable(rng: &mut StdRng) -> Consumable {
-        match rng.gen_range(0..4) {
-            0 => Consumable { name: "Fiery ale".to_string(), heal: 2, atk_bonus: 2, def_bonus: 0 },
-            1 => Consumable { name: "Weeping Willow bark".to_string(), heal: 3, atk_bonus: 0, def_bonus: 0 },
-            2 => Consumable { name: "Sunny Jerky".to_string(), heal: 5, atk_bonus: 0, def_bonus: 0 },
-            _ => Consumable { name: "Frozen tears".to_string(), heal: -2, atk_bonus: 0, def_bonus: 5 },
-        }
+        let items = item::registry();
+        let loot = items.chest_loot();
+        let id = &loot[rng.gen_range(0..loot.len())];
+        items.new_consumable(id).expect("chest loot is checked against the catalogue")
     }
 
     fn scatter_chests(map: &mut Map, seed: u64, spawn: (i32, i32), door: (i32, i32), target_count: usize) -> Vec<Chest> {
@@ -370,6 +380,9 @@ impl World {
             }
         }
         let mut rng = StdRng::seed_from_u64(seed);
+        // Kept apart so adding gold didn't move the chests of existing seeds.
+        let mut gold_rng = StdRng::seed_from_u64(seed ^ 0x601D);
+        let mut gear_rng = StdRng::seed_from_u64(seed ^ 0x6EA2);
         let mut chests = Vec::new();
         let mut exclude = vec![spawn, door];
         let count = target_count.min(floors.len());
@@ -381,7 +394,13 @@ impl World {
             }
             exclude.push(pos);
             map.set(pos.0 as usize, pos.1 as usize, Tile::Chest);
-            chests.push(Chest { x: pos.0, y: pos.1, item: Some(Self::random_consumable(&mut rng)), weapon: None, opened: false });
+            let item = Some(Self::random_consumable(&mut rng));
+            let (item, weapon) = if gear_rng.gen_bool(Self::GEAR_CHANCE) {
+                (None, Some(rarity::roll_equipment(item::registry(), &mut gear_rng)))
+            } else {
+                (item, None)
+            };
+            chests.push(Chest { x: pos.0, y: pos.1, item, weapon, gold: gold_rng.gen_range(2..=10), opened: false });
         }
         chests
     }
@@ -411,27 +430,34 @@ impl World {
         }
         self.player.x = spawn.0;
         self.player.y = spawn.1;
+        self.autosave_requested = true;
         if new_room == 1 { self.push_log("You step through the door into Room 2...".to_string()); } 
         else { self.push_log("You step back into Room 1...".to_string()); }
     }
 
     fn toggle_inventory(&mut self) {
         self.inventory_open = !self.inventory_open;
-        if self.inventory_open { self.stats_open = false; self.push_log("Inventory opened.".to_string()); } 
+        if self.inventory_open { self.stats_open = false; self.journal_open = false; self.push_log("Inventory opened.".to_string()); } 
         else { self.push_log("Inventory closed.".to_string()); }
     }
 
     fn toggle_stats(&mut self) {
         self.stats_open = !self.stats_open;
-        if self.stats_open { self.inventory_open = false; self.push_log("Stats opened.".to_string()); } 
+        if self.stats_open { self.inventory_open = false; self.journal_open = false; self.push_log("Stats opened.".to_string()); } 
         else { self.push_log("Stats closed.".to_string()); }
     }
 
+    fn toggle_journal(&mut self) {
+        self.journal_open = !self.journal_open;
+        if self.journal_open { self.inventory_open = false; self.stats_open = false; self.push_log("Journal opened.".to_string()); }
+        else { self.push_log("Journal closed.".to_string()); }
+    }
+
     fn toggle_inventory_tab(&mut self) {
         let tab_before = self.player.inventory.tab;
         self.player.inventory.toggle_tab();
         let tab_after = self.player.inventory.tab;
-        let name = match tab_after { InvTab::Weapons => "Weapons", InvTab::Consumables => "Consumables", InvTab::Backpack => "Backpack" };
+        let name = match tab_after { InvTab::Equipment => "Equipment", InvTab::Consumables => "Consumables", InvTab::Backpack => "Backpack" };
         if tab_before != tab_after { self.push_log(format!("Inventory tab: {}", name)); }
     }
 
@@ -440,63 +466,35 @@ impl World {
         let mut log_msg: Option<String> = None;
 
         match selection {
-            InvSelection::SwordSlot => {
-                let eq_opt = self.player.inventory.sword.take();
-                if let Some(eq) = eq_opt {
-                    self.player.max_hp -= eq.hp_bonus; 
-                    if self.player.hp > self.player.max_hp { self.player.hp = self.player.max_hp; }
-                    self.player.inventory.backpack.push(eq.clone());
+            InvSelection::Gear(slot) => {
+                if let Some(bag) = self.player.inventory.bag_in_the_way(&[slot], None) {
+                    log_msg = Some(format!("Empty your {} first.", bag.name));
+                } else if let Some(eq) = self.player.unequip(slot) {
                     log_msg = Some(format!("Unequipped {}.", eq.name));
-                } else { log_msg = Some("No sword equipped.".to_string()); }
-            }
-            InvSelection::ShieldSlot => {
-                let eq_opt = self.player.inventory.shield.take();
-                if let Some(eq) = eq_opt {
-                    self.player.max_hp -= eq.hp_bonus;
-                    if self.player.hp > self.player.max_hp { self.player.hp = self.player.max_hp; }
-                    self.player.inventory.backpack.push(eq.clone());
-                    log_msg = Some(format!("Unequipped {}.", eq.name));
-                } else { log_msg = Some("No shield equipped.".to_string()); }
+                    self.player.inventory.backpack.push(eq);
+                } else { log_msg = Some(format!("Nothing worn on {}.", slot.label().to_lowercase())); }
             }
             InvSelection::Consumable(_) => {
                 let item_opt = self.player.inventory.take_selected_consumable();
                 if let Some(item) = item_opt {
-                    let before = self.player.hp;
-                    self.player.hp = (self.player.hp + item.heal).min(self.player.max_hp);
-                    let healed = self.player.hp - before;
-                    if item.atk_bonus != 0 || item.def_bonus != 0 {
-                        self.player.add_temp_buff(item.atk_bonus, item.def_bonus, 0, Duration::from_secs(30));
-                    }
-                    let mut effects = vec![Self::fmt_hp_delta(healed)];
-                    let fmt_signed = |v: i32| if v >= 0 { format!("+{}", v) } else { format!("{}", v) };
-                    if item.atk_bonus != 0 { effects.push(format!("{} ATK/30sec", fmt_signed(item.atk_bonus))); }
-                    if item.def_bonus != 0 { effects.push(format!("{} DEF/30sec", fmt_signed(item.def_bonus))); }
-                    log_msg = Some(format!("Used {} ({}).", item.name, effects.join(", ")));
+                    log_msg = Some(self.consume(&item));
                 } else { log_msg = Some("No consumables to use.".to_string()); }
             }
             InvSelection::BackpackItem(i) => {
                 let eq_opt = if i < self.player.inventory.backpack.len() { Some(self.player.inventory.backpack.remove(i)) } else { None };
                 if let Some(eq) = eq_opt {
-                    self.player.max_hp += eq.hp_bonus;
-                    match eq.slot {
-                        Slot::Sword => {
-                            if let Some(old) = self.player.inventory.sword.take() { 
-                                self.player.max_hp -= old.hp_bonus;
-                                self.player.inventory.backpack.push(old); 
-                            }
-                            self.player.inventory.sword = Some(eq.clone());
-                            log_msg = Some(format!("Equipped sword: {}.", eq.name));
+                    let name = eq.name.clone();
+                    match self.player.equip(eq) {
+                        Ok((slot, removed)) => {
+                            let mut msg = format!("Equipped {}: {}.", slot.label().to_lowercase(), name);
+                            if !removed.is_empty() { msg.push_str(&format!(" Took off {}.", removed.join(" and "))); }
+                            log_msg = Some(msg);
                         }
-                        Slot::Shield => {
-                            if let Some(old) = self.player.inventory.shield.take() { 
-                                self.player.max_hp -= old.hp_bonus;
-                                self.player.inventory.backpack.push(old); 
-                            }
-                            self.player.inventory.shield = Some(eq.clone());
-                            log_msg = Some(format!("Equipped shield: {}.", eq.name));
+                        Err(blocked) => {
+                            self.player.inventory.backpack.insert(i, *blocked.item);
+                            log_msg = Some(format!("Empty your {} first.", blocked.bag));
                         }
                     }
-                    if self.player.hp > self.player.max_hp { self.player.hp = self.player.max_hp; }
                     let inv = &mut self.player.inventory;
                     if inv.backpack.is_empty() { inv.backpack_cursor = 0; } else if inv.backpack_cursor >= inv.backpack.len() { inv.backpack_cursor = inv.backpack.len() - 1; }
                 } else { log_msg = Some("Nothing to equip.".to_string()); }
@@ -506,19 +504,81 @@ impl World {
         if let Some(m) = log_msg { self.push_log(m); }
     }
 
+    /// Heals, buffs and applies the statuses of a consumable, wherever it was
+    /// used from. Returns the log line.
+    fn consume(&mut self, item: &Consumable) -> String {
+        let before = self.player.hp;
+        self.player.heal(item.heal);
+        let healed = self.player.hp - before;
+        if item.atk_bonus != 0 || item.def_bonus != 0 {
+            self.player.add_temp_buff(item.atk_bonus, item.def_bonus, 0, BUFF_TURNS);
+        }
+        let mut effects = vec![Self::fmt_hp_delta(healed)];
+        let fmt_signed = |v: i32| if v >= 0 { format!("+{}", v) } else { format!("{}", v) };
+        if item.atk_bonus != 0 { effects.push(format!("{} ATK/{} turns", fmt_signed(item.atk_bonus), BUFF_TURNS)); }
+        if item.def_bonus != 0 { effects.push(format!("{} DEF/{} turns", fmt_signed(item.def_bonus), BUFF_TURNS)); }
+        for spec in &item.statuses {
+            status::apply(&mut self.player.statuses, spec.kind, spec.turns);
+            effects.push(format!("{} {} turns", spec.kind.label(), spec.turns));
+        }
+        format!("Used {} ({}).", item.name, effects.join(", "))
+    }
+
     fn start_chest_dialogue(&mut self, room: usize, x: i32, y: i32, item: Option<Consumable>, weapon: Option<Equipment>) {
-        let name = if let Some(c) = &item { c.name.clone() } else if let Some(w) = &weapon { w.name.clone() } else { "nothing".to_string() };
+        let name = if let Some(c) = &item { c.name.clone() } else if let Some(w) = &weapon { format!("{} ({})", w.name, w.rarity.label()) } else { "nothing".to_string() };
+        let take = match &item {
+            Some(c) if !self.player.inventory.can_hold(c) => "Swap with an existing item",
+            _ => "Put in inventory",
+        };
         let pages = vec![format!(
-            "You found a treasure chest!\nInside is: {}\n\n(A) Put in inventory\n(B) Use now (Consumable)\n(C) Throw away",
-            name
+            "You found a treasure chest!\nInside is: {}\n\n(A) {}\n(B) Use now (Consumable)\n(C) Throw away",
+            name, take
         )];
         self.dialogue = Some(DialogueSession {
-            npc: NpcId::MayorSol, title: "Treasure Chest".to_string(), pages, page_index: 0,
-            awaiting: Some(AwaitingChoice::Chest { room, x, y, item, weapon }),
+            npc: None, node: None, title: "Treasure Chest".to_string(), pages, page_index: 0,
+            awaiting: Some(AwaitingChoice::Chest { room, x, y, item, weapon: weapon.map(Box::new) }),
         });
         self.state = GameState::Dialogue;
     }
 
+    /// Consumable slots holding a single item, the only ones a swap can free.
+    fn swappable(&self) -> Vec<usize> {
+        self.player.inventory.consumables.iter().enumerate().filter(|(_, s)| s.count == 1).map(|(i, _)| i).collect()
+    }
+
+    /// Lists the single items by letter, A-D a page at a time, so one can go
+    /// back in the chest to make way for `item`. The key map keeps these
+    /// letters (and N, Z) unbound in dialogue.
+    fn start_swap_dialogue(&mut self, item: Consumable, page: usize) {
+        let singles = self.swappable();
+        let mut text = format!("Your consumables are full. Swap the {} for:\n", item.name);
+        if singles.is_empty() {
+            text = format!("Your consumables are full, and every slot holds a stack of several, so none can make way for the {}.\n", item.name);
+        }
+        let stacks = &self.player.inventory.consumables;
+        for (letter, &i) in ('A'..).zip(singles.iter().skip(page * SWAP_PAGE).take(SWAP_PAGE)) {
+            text.push_str(&format!("({}) {}\n", letter, stacks[i].label()));
+        }
+        if singles.len() > SWAP_PAGE { text.push_str("(N) More\n"); }
+        text.push_str("\n(Z) Leave it");
+        self.dialogue = Some(DialogueSession {
+            npc: None, node: None, title: "Treasure Chest".to_string(), pages: vec![text], page_index: 0,
+            awaiting: Some(AwaitingChoice::Swap { item, page }),
+        });
+        self.state = GameState::Dialogue;
+    }
+
+    /// Closes the chest under the player again with `item` inside.
+    fn restock_chest(&mut self, item: Consumable) {
+        let (px, py) = (self.player.x, self.player.y);
+        let level = &mut self.levels[self.current];
+        if let Some(chest) = level.chests.iter_mut().find(|c| c.x == px && c.y == py) {
+            chest.item = Some(item);
+            chest.opened = false;
+            level.map.set(px as usize, py as usize, Tile::Chest);
+        }
+    }
+
     fn open_chest_if_on_one(&mut self) {
         let room = self.current;
         let px = self.player.x;
@@ -529,153 +589,228 @@ impl World {
             level.map.set(px as usize, py as usize, Tile::Floor);
             let item = chest.item.take();
             let weapon = chest.weapon.take();
+            let gold = std::mem::take(&mut chest.gold);
+            if gold > 0 {
+                self.player.gold += gold;
+                self.push_log(format!("Found {} gold.", gold));
+            }
             self.start_chest_dialogue(room, px, py, item, weapon);
         }
     }
 
     // --- BATTLE LOGIC ---
     fn start_battle(&mut self, enemy_id: NpcId) {
-        let (name, hp, atk, def, spd) = match enemy_id {
-            NpcId::Shab => ("Shab", 10, 3, 0, 4),
-            NpcId::Krad => ("Krad", 20, 6, 4, 0),
-            NpcId::Mah => ("Mah", 30, 12, 10, 8),
-            // Final Boss
-            NpcId::MayorSol => ("Mayor Sol", 40, 10, 30, 0),
-            _ => return,
-        };
+        let Some(def) = npc::registry().get(&enemy_id) else { return };
+        let Some(stats) = &def.combat else { return };
+        let mut enemies = vec![Enemy::new(enemy_id.clone(), def.name.clone(), stats)];
+        enemies.extend(stats.allies.iter().map(|a| Enemy::new(enemy_id.clone(), a.name.clone(), &a.stats)));
+
+        // "Weeping Guard 1", "Weeping Guard 2" when several share a name.
+        let names: Vec<String> = enemies.iter().map(|e| e.name.clone()).collect();
+        for (i, e) in enemies.iter_mut().enumerate() {
+            if names.iter().filter(|n| **n == names[i]).count() > 1 {
+                let nth = names[..i].iter().filter(|n| **n == names[i]).count() + 1;
+                e.name = format!("{} {}", names[i], nth);
+            }
+        }
 
+        let names: Vec<&str> = enemies.iter().map(|e| e.name.as_str()).collect();
+        let listed = match names.split_last() {
+            Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+            _ => names.concat(),
+        };
+        self.push_log(format!("Battle started against {}!", listed));
         self.battle = Some(BattleSession {
-            enemy_id,
-            enemy_name: name.to_string(),
-            enemy_hp: hp,
-            enemy_max_hp: hp,
-            enemy_atk: atk,
-            enemy_def: def,
-            enemy_speed: spd,
+            enemies,
+            target: 0,
+            pending: None,
             penalty_mode: false,
-            player_initiated: false, 
+            player_initiated: false,
+            cooldowns: BTreeMap::new(),
+            skills_open: false,
+            skill_cursor: 0,
         });
         self.state = GameState::Battle;
-        self.push_log(format!("Battle started against {}!", name));
     }
 
-    fn calc_damage(atk: i32) -> i32 {
-        (atk as f32 * 1.2) as i32
+    fn apply_battle_turn(&mut self, choice: TurnChoice, penalty: bool) {
+        let Some(mut bs) = self.battle.take() else { return };
+        if penalty { bs.penalty_mode = true; }
+        bs.skills_open = false;
+        bs.cooldowns.retain(|_, turns| { *turns -= 1; *turns > 0 });
+        self.tick_buffs();
+
+        let rules = TurnRules { penalty_mode: bs.penalty_mode, player_initiated: bs.player_initiated };
+        let turn = combat::resolve_turn(&mut self.player, &mut bs.enemies, bs.target, rules, choice, &mut self.rng);
+        let names: Vec<String> = bs.enemies.iter().map(|e| e.name.clone()).collect();
+        let mut say = None;
+        for event in &turn.events {
+            self.push_log(event.message(&names));
+            if let CombatEvent::PhaseChanged { enemy, phase } = event {
+                say = Some((*enemy, bs.enemies[*enemy].ai.phases[*phase].say.clone()));
+            }
+        }
+
+        match turn.outcome {
+            None => {
+                if bs.enemies[bs.target].is_down() {
+                    bs.target = bs.enemies.iter().position(|e| !e.is_down()).unwrap_or(0);
+                }
+                // A boss changing phase speaks up; the battle resumes when the dialogue closes.
+                if let Some((enemy, say)) = say
+                    && !say.is_empty()
+                {
+                    self.start_dialogue_raw(&names[enemy], say);
+                }
+                self.battle = Some(bs);
+            }
+            Some(outcome) => {
+                if outcome == Outcome::Won {
+                    let leader = bs.enemies.swap_remove(0);
+                    self.handle_win(leader.id);
+                }
+                self.state = GameState::Playing;
+            }
+        }
     }
 
-    fn try_deflect(def: i32) -> bool {
-        let chance = (def as f32 / 10.0) * 0.2;
-        rand::random::<f32>() < chance
+    /// Skills usable in battle: those whose unlock conditions hold, then any
+    /// granted by equipped items.
+    pub fn known_skills(&self) -> Vec<&'static SkillDef> {
+        let inv = &self.player.inventory;
+        let granted: Vec<&str> = inv.worn()
+            .flat_map(|e| e.skills.iter().map(String::as_str))
+            .collect();
+        skill::registry().all().iter()
+            .filter(|s| (!s.unlock_when.is_empty() && self.check_all(&s.unlock_when)) || granted.contains(&s.id.as_str()))
+            .collect()
     }
 
-    fn apply_battle_turn(&mut self, opt: u8, penalty: bool) {
-        let mut end_battle = false;
-        let mut player_won = false;
+    /// Battle turns until a skill can be used again; 0 when it's ready.
+    pub fn skill_cooldown(&self, id: &str) -> u32 {
+        self.battle.as_ref().and_then(|b| b.cooldowns.get(id)).copied().unwrap_or(0)
+    }
 
-        if let Some(mut bs) = self.battle.take() {
-            if penalty { bs.penalty_mode = true; }
-            let p_spd = self.player.speed();
-            let e_spd = bs.enemy_speed;
-            let player_first = if bs.penalty_mode { false } else { p_spd >= e_spd };
+    /// Buffs only wear down while the game moves on: a step on the map or a battle turn.
+    fn tick_buffs(&mut self) {
+        if self.player.tick_buffs() {
+            self.push_log("A boost wore off.");
+        }
+    }
 
-            match opt {
-                1 => { // Fight
-                    if player_first {
-                        self.perform_player_attack(&mut bs);
-                        if bs.enemy_hp > 0 { self.perform_enemy_attack(&mut bs); }
-                    } else {
-                        self.perform_enemy_attack(&mut bs);
-                        if self.player.hp > 0 { self.perform_player_attack(&mut bs); }
-                    }
-                }
-                2 => { // Inventory Used
-                    self.perform_enemy_attack(&mut bs);
-                }
-                3 => { // Run
-                    if bs.player_initiated {
-                        self.push_log("You started this, finish it!");
-                        self.perform_enemy_attack(&mut bs);
-                    } else {
-                        if rand::random::<f32>() < 0.5 {
-                            self.push_log("You fled the battle!");
-                            end_battle = true;
-                        } else {
-                            self.push_log("Failed to flee!");
-                            self.perform_enemy_attack(&mut bs);
-                        }
-                    }
-                }
-                _ => {}
-            }
+    fn toggle_skills(&mut self) {
+        let count = self.known_skills().len();
+        let Some(bs) = &mut self.battle else { return };
+        if !bs.skills_open && count == 0 {
+            self.push_log("You haven't learned any skills yet.");
+            return;
+        }
+        bs.skills_open = !bs.skills_open;
+        bs.skill_cursor = bs.skill_cursor.min(count.saturating_sub(1));
+        self.inventory_open = false;
+    }
 
-            if bs.enemy_hp <= 0 {
-                player_won = true;
-                end_battle = true;
-            }
-            if self.player.hp <= 0 {
-                end_battle = true;
-            }
+    fn move_skill_cursor(&mut self, delta: i32) {
+        let count = self.known_skills().len() as i32;
+        if let Some(bs) = &mut self.battle
+            && bs.skills_open
+            && count > 0
+        {
+            bs.skill_cursor = (bs.skill_cursor as i32 + delta).rem_euclid(count) as usize;
+        }
+    }
 
-            if !end_battle {
-                self.battle = Some(bs);
-            } else {
-                if player_won {
-                    self.handle_win(bs.enemy_id);
-                }
-                self.state = GameState::Playing;
+    fn use_selected_skill(&mut self) {
+        let Some(cursor) = self.battle.as_ref().map(|b| b.skill_cursor) else { return };
+        let Some(def) = self.known_skills().get(cursor).copied() else { return };
+        let wait = self.skill_cooldown(&def.id);
+        if wait > 0 {
+            self.push_log(format!("{} needs {} more turn(s).", def.name, wait));
+            return;
+        }
+        if !matches!(def.effect, SkillEffect::Guard { .. }) && self.choose_target(PendingAttack::Skill(def.id.clone())) {
+            return;
+        }
+        self.use_skill(def);
+    }
+
+    fn use_skill(&mut self, def: &SkillDef) {
+        self.push_log(format!("You use {}!", def.name));
+        self.apply_battle_turn(TurnChoice::Skill(def.effect), false);
+        if let Some(bs) = &mut self.battle
+            && def.cooldown > 0
+        {
+            bs.cooldowns.insert(def.id.clone(), def.cooldown);
+        }
+    }
+
+    /// Holds an attack back until a target is picked, when there is more than
+    /// one enemy to pick from. False if the attack can go ahead now.
+    fn choose_target(&mut self, attack: PendingAttack) -> bool {
+        let Some(bs) = &mut self.battle else { return false };
+        if bs.enemies.iter().filter(|e| !e.is_down()).count() < 2 {
+            return false;
+        }
+        bs.pending = Some(attack);
+        bs.skills_open = false;
+        true
+    }
+
+    /// Moves the target marker to the next enemy still standing.
+    fn move_target(&mut self, delta: i32) {
+        let Some(bs) = &mut self.battle else { return };
+        let n = bs.enemies.len() as i32;
+        let mut i = bs.target as i32;
+        for _ in 0..n {
+            i = (i + delta).rem_euclid(n);
+            if !bs.enemies[i as usize].is_down() {
+                bs.target = i as usize;
+                return;
             }
         }
     }
 
-    fn perform_player_attack(&mut self, bs: &mut BattleSession) {
-        let dmg = Self::calc_damage(self.player.attack());
-        if Self::try_deflect(bs.enemy_def) {
-            self.push_log(format!("{} deflected your attack!", bs.enemy_name));
-        } else {
-            bs.enemy_hp -= dmg;
-            self.push_log(format!("You hit {} for {} dmg.", bs.enemy_name, dmg));
+    fn confirm_target(&mut self) {
+        let Some(attack) = self.battle.as_mut().and_then(|b| b.pending.take()) else { return };
+        match attack {
+            PendingAttack::Fight => self.apply_battle_turn(TurnChoice::Fight, false),
+            PendingAttack::Skill(id) => {
+                if let Some(def) = skill::registry().get(&id) { self.use_skill(def); }
+            }
         }
     }
 
-    fn perform_enemy_attack(&mut self, bs: &mut BattleSession) {
-        let dmg = Self::calc_damage(bs.enemy_atk);
-        if Self::try_deflect(self.player.defense()) {
-            self.push_log(format!("You deflected {}'s attack!", bs.enemy_name));
-        } else {
-            self.player.hp -= dmg;
-            self.push_log(format!("{} hit you for {} dmg.", bs.enemy_name, dmg));
+    fn cancel_target(&mut self) {
+        if let Some(bs) = &mut self.battle
+            && let Some(attack) = bs.pending.take()
+        {
+            bs.skills_open = matches!(attack, PendingAttack::Skill(_));
         }
     }
 
     fn handle_win(&mut self, id: NpcId) {
-        // NEW LOGIC: Restore HP and boost Stats after any battle win
-        self.player.hp = self.player.max_hp;
-        self.player.base_attack += 3;
-        self.player.base_defense += 3;
-        self.player.base_speed += 3;
-        self.push_log("HP restored & Stats increased (+3)!");
-
-        match id {
-            NpcId::Shab => {
-                self.shab_defeated = true;
+        self.player.hp = self.player.max_hp();
+        self.player.statuses.clear();
+        self.push_log("HP restored!");
+        self.award_xp(&id);
+        let gold = self.award_loot(&id);
+        self.set_flag(npc::defeated_flag(&id));
+
+        match id.as_str() {
+            "shab" => {
                 self.start_dialogue_raw("Shab", vec!["I can’t believe I lost to the likes of you…".to_string()]);
             }
-            NpcId::Krad => {
-                self.krad_defeated = true;
+            "krad" => {
                 self.start_dialogue_raw("Krad", vec!["My armor….".to_string()]);
             }
-            NpcId::Mah => {
-                self.mah_defeated = true;
-                let boss_pos = if let Some(pos) = self.npcs.iter().position(|n| n.id == NpcId::Mah) {
+            "mah" => {
+                let boss_pos = if let Some(pos) = self.npcs.iter().position(|n| n.id == id) {
                     let npc = self.npcs.remove(pos);
                     let chest = Chest {
                         x: npc.x, y: npc.y,
                         item: None,
-                        weapon: Some(Equipment {
-                            name: "Weeping Dagger".to_string(),
-                            slot: Slot::Sword,
-                            hp_bonus: -100, atk_bonus: -100, def_bonus: -100, speed_bonus: -100,
-                        }),
+                        weapon: item::registry().new_equipment(&ItemId::new("weeping_dagger")),
+                        gold: 0,
                         opened: false
                     };
                     self.levels[1].chests.push(chest);
@@ -690,11 +825,8 @@ impl World {
                     let shield_chest = Chest {
                         x: sx, y: sy,
                         item: None,
-                        weapon: Some(Equipment {
-                            name: "Shield of healing".to_string(),
-                            slot: Slot::Shield,
-                            hp_bonus: 2, atk_bonus: 0, def_bonus: 10, speed_bonus: 0,
-                        }),
+                        weapon: item::registry().new_equipment(&ItemId::new("shield_of_healing")),
+                        gold: 0,
                         opened: false
                     };
                     self.levels[1].chests.push(shield_chest);
@@ -706,18 +838,100 @@ impl World {
                     "Listen, Sol, is not…".to_string(), "what".to_string(), "you".to_string(), "thin-".to_string()
                 ]);
             }
-            NpcId::MayorSol => {
-                self.mayor_defeated = true;
+            "mayor_sol" => {
                 self.start_dialogue_raw("Mayor Sol", vec!["NOOOOOO, THE SHAREHOLDERSSSSSSSS".to_string()]);
             }
             _ => {}
         }
-        self.push_log("You won the battle!");
+        if gold > 0 {
+            self.push_log(format!("You won the battle and picked up {} gold!", gold));
+        } else {
+            self.push_log("You won the battle!");
+        }
+        self.autosave_requested = true;
+    }
+
+    /// XP for the whole group led by `id`.
+    fn award_xp(&mut self, id: &NpcId) {
+        let Some(stats) = npc::registry().get(id).and_then(|d| d.combat.as_ref()) else { return };
+        let xp = stats.xp() + stats.allies.iter().map(|a| a.stats.xp()).sum::<u32>();
+        self.push_log(format!("You gained {} XP.", xp));
+        if self.player.exp.gain(xp) > 0 {
+            self.push_log(format!(
+                "Level up! You are now level {}. Press Q to spend {} stat point(s).",
+                self.player.exp.level, self.player.exp.points,
+            ));
+        }
+    }
+
+    /// Rolls the group's item drops and pays out its gold. Returns the gold, which the
+    /// win message reports so the log keeps room for the rest of the battle.
+    fn award_loot(&mut self, id: &NpcId) -> u32 {
+        let Some(stats) = npc::registry().get(id).and_then(|d| d.combat.as_ref()) else { return 0 };
+        let gold = stats.gold + stats.allies.iter().map(|a| a.stats.gold).sum::<u32>();
+        self.player.gold += gold;
+        let drops = stats.drops.iter().chain(stats.allies.iter().flat_map(|a| &a.stats.drops));
+        for drop in drops {
+            if self.rng.r#gen::<f32>() >= drop.chance { continue; }
+            let Some(found) = item::registry().new_consumable(&drop.item) else { continue };
+            let name = found.name.clone();
+            if self.player.inventory.add_consumable(found) {
+                self.push_log(format!("Found {} among the spoils.", name));
+            } else {
+                self.push_log(format!("No room for {}; you leave it behind.", name));
+            }
+        }
+        gold
+    }
+
+    fn move_stat_cursor(&mut self, delta: i32) {
+        let n = StatChoice::ALL.len() as i32;
+        self.stat_cursor = (self.stat_cursor as i32 + delta).rem_euclid(n) as usize;
+    }
+
+    fn spend_stat_point(&mut self) {
+        let choice = StatChoice::ALL[self.stat_cursor];
+        if self.player.spend_point(choice) {
+            self.push_log(format!("{} +{}.", choice.label(), choice.per_point()));
+        }
+    }
+
+    fn open_shop(&mut self, mode: ShopMode) {
+        self.inventory_open = false;
+        self.stats_open = false;
+        self.journal_open = false;
+        if mode == ShopMode::Sell && self.player.inventory.tab == InvTab::Equipment {
+            self.player.inventory.tab = InvTab::Consumables;
+        }
+        self.shop = Some(ShopSession { mode, cursor: 0 });
+    }
+
+    fn move_shop_cursor(&mut self, delta: i32) {
+        let Some(s) = &mut self.shop else { return };
+        match s.mode {
+            ShopMode::Buy => {
+                let n = item::registry().shop().len().max(1) as i32;
+                s.cursor = (s.cursor as i32 + delta).rem_euclid(n) as usize;
+            }
+            ShopMode::Sell => self.player.inventory.move_cursor(delta),
+        }
+    }
+
+    fn shop_confirm(&mut self) {
+        let Some(s) = &self.shop else { return };
+        let result = match s.mode {
+            ShopMode::Buy => shop::buy(&mut self.player, item::registry(), s.cursor),
+            ShopMode::Sell => shop::sell(&mut self.player, item::registry()),
+        };
+        match result {
+            Ok(msg) | Err(msg) => self.push_log(msg),
+        }
     }
 
     fn start_dialogue_raw(&mut self, title: &str, pages: Vec<String>) {
         self.dialogue = Some(DialogueSession {
-            npc: NpcId::Random1,
+            npc: None,
+            node: None,
             title: title.to_string(),
             pages,
             page_index: 0,
@@ -726,192 +940,169 @@ impl World {
         self.state = GameState::Dialogue;
     }
 
-    fn swap_dorosht_item(&mut self) -> bool {
-        let mut found = false;
-        let mut idx_to_remove = None;
-        let mut from_backpack = false;
+    fn has_item(&self, id: &ItemId) -> bool {
+        let inv = &self.player.inventory;
+        inv.worn().any(|e| &e.id == id)
+            || inv.backpack.iter().any(|e| &e.id == id)
+    }
 
-        if let Some(ref s) = self.player.inventory.sword {
-            if s.name == "Weeping Dagger" {
-                found = true;
-            }
+    fn check(&self, cond: &Condition) -> bool {
+        let inv = &self.player.inventory;
+        match cond {
+            Condition::Flag(f) => self.flag(f),
+            Condition::HasItem(id) => self.has_item(id),
+            Condition::Equipped(kind) => inv.wearing(*kind),
+            Condition::Not(c) => !self.check(c),
         }
-        
-        if found {
-            self.player.inventory.sword = None;
-            self.player.max_hp += 100; 
-        } else {
-            for (i, item) in self.player.inventory.backpack.iter().enumerate() {
-                if item.name == "Weeping Dagger" {
-                    idx_to_remove = Some(i);
-                    from_backpack = true;
-                    found = true;
-                    break;
-                }
-            }
+    }
+
+    fn check_all(&self, conds: &[Condition]) -> bool {
+        conds.iter().all(|c| self.check(c))
+    }
+
+    /// A door's locked text, plus any gear it wants that isn't worn yet.
+    fn locked_text(&self, rule: &DoorRule) -> String {
+        let missing: Vec<&str> = rule.requires.iter()
+            .filter(|c| !self.check(c))
+            .filter_map(|c| match c { Condition::Equipped(kind) => Some(kind.noun()), _ => None })
+            .collect();
+        if missing.is_empty() { return rule.locked.clone(); }
+        format!("{}. Still missing: {}.", rule.locked.trim_end_matches('.'), missing.join(", "))
+    }
+
+    /// Removes an item by id, preferring equipped slots, and undoes its max HP bonus.
+    fn take_item(&mut self, id: &ItemId) -> bool {
+        let worn = self.player.inventory.equipped.iter().find(|(_, e)| &e.id == id).map(|(slot, _)| *slot);
+        if let Some(slot) = worn {
+            self.player.unequip(slot);
+            return true;
+        }
+        let inv = &mut self.player.inventory;
+        if let Some(i) = inv.backpack.iter().position(|e| &e.id == id) {
+            inv.backpack.remove(i);
+            return true;
         }
+        false
+    }
 
-        if found {
-            if from_backpack {
-                if let Some(i) = idx_to_remove {
-                    self.player.inventory.backpack.remove(i);
+    fn apply_effect(&mut self, effect: &Effect, npc: Option<&NpcId>) {
+        match effect {
+            Effect::SetFlag(f) => self.set_flag(f.clone()),
+            Effect::ClearFlag(f) => { self.flags.remove(f); }
+            Effect::Equip(id) => {
+                let Some(eq) = item::registry().new_equipment(id) else { return };
+                if let Err(blocked) = self.player.equip(eq) {
+                    self.push_log(format!("{} went in your backpack. Empty your {} to wear it.", blocked.item.name, blocked.bag));
+                    self.player.inventory.backpack.push(*blocked.item);
                 }
             }
-            let axe = Equipment {
-                name: "Willow Axe".to_string(),
-                slot: Slot::Sword,
-                hp_bonus: 5, atk_bonus: 15, def_bonus: 5, speed_bonus: -2,
-            };
-            self.player.inventory.backpack.push(axe);
-            return true;
+            Effect::GiveItem(id) => self.player.inventory.backpack.extend(item::registry().new_equipment(id)),
+            Effect::TakeItem(id) => { self.take_item(id); }
+            Effect::StartBattle => {
+                if let Some(id) = npc { self.start_battle(id.clone()); }
+            }
+            Effect::SetState(state) => self.state = state.clone(),
+            Effect::Log(msg) => self.push_log(msg.clone()),
+            Effect::OpenShop(mode) => self.open_shop(*mode),
         }
-        false
+    }
+
+    /// Shows a node of an NPC's dialogue graph and runs its on-enter effects.
+    fn enter_node(&mut self, npc: &NpcId, title: String, node_id: &str) {
+        let Some(node) = npc::registry().get(npc).and_then(|d| d.dialogue.node(node_id)) else { return };
+
+        self.dialogue = Some(DialogueSession {
+            npc: Some(npc.clone()),
+            node: Some(node_id.to_string()),
+            title,
+            pages: node.pages.clone(),
+            page_index: 0,
+            awaiting: if node.choices.is_empty() { None } else { Some(AwaitingChoice::Choices) },
+        });
+        self.state = GameState::Dialogue;
+
+        for e in &node.effects { self.apply_effect(e, Some(npc)); }
     }
 
     fn start_dialogue_for(&mut self, npc: &Npc) {
-        let session = match npc.id {
-            // Existing NPCs
-            NpcId::MayorSol => {
-                 if self.mayor_defeated {
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["...".to_string()], page_index: 0, awaiting: None }
-                 } else if self.dorosht_completed && self.mah_defeated {
-                     DialogueSession { 
-                         npc: npc.id, 
-                         title: npc.name.clone(), 
-                         pages: vec![
-                             "GREEEAAAAT JOB!!!!!!".to_string(),
-                             "YOU DID IT!!!!".to_string(),
-                             "but".to_string(),
-                             "It was for me...".to_string(),
-                             "You see, I LIED. To you, to this town, TO EVERYONE!".to_string(),
-                             "I told them, this sunshine drought was due to the Weeping, I even told them lies of their namesake, but it was for a good reason you see...".to_string(),
-                             "FOR MONEY! MUAHAHAHA".to_string(),
-                             "CAPATALISM SHALL PREVAIL!!!!".to_string()
-                         ], 
-                         page_index: 0, 
-                         awaiting: None 
-                     }
-                 } else if self.mayor_done { 
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Well, what’re you still standing here for? GO TO NOOR!".to_string()], page_index: 0, awaiting: None } 
-                 } else { 
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Welcome to Sunny Days, visitor! I am Mayor Sol. We are normally much more able to take in tourists, but you may have arrived at a bad time. The Weeping have made it a rough time, they have completely taken over the Weeping Willow forests.".to_string(), "What’s that? The weeping sound like they belong in the Weeping Willow Forests? No! That’s nonsense, the only reason they are called the weeping, is because they WEEP before they kill! I mean, is it not right there in the name? Keep up! Ok, but my friend, you MUST help us get them out. Without our Weeping Willow bark, we are losing our health! Please will you help? (Y/N)".to_string()], page_index: 0, awaiting: Some(AwaitingChoice::YesNoMayor) } 
-                 }
-            },
-            NpcId::Noor => {
-                 if self.dorosht_completed && self.mah_defeated {
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Always knew that SOB was no good, its up to you....".to_string()], page_index: 0, awaiting: None }
-                 } else if self.noor_done { 
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Scram! Go to Lamp and get whatever you’re missing!!".to_string()], page_index: 0, awaiting: None } 
-                 } else { 
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Hey there partner!".to_string(), "What’s that, the Mayor sent you here? Damn Sol, always ruining my day. What! No not you, you seem okay… ish. So you’re gonna go and fight the Weeping ay? Well you’ll need a weapon. Grab one: (A) Basic Sword  (B) Basic Shield".to_string(), "Good choice! Now I’ll keep the other one to be fair, if you want your second choice, go see Lamp!".to_string()], page_index: 0, awaiting: Some(AwaitingChoice::ABNoorWeapon) } 
-                 }
-            },
-            NpcId::Lamp => {
-                 if self.dorosht_completed && self.mah_defeated {
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["I-I can't believe it....".to_string()], page_index: 0, awaiting: None }
-                 } else if !self.noor_done { 
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Hey aren’t you supposed to talk to Noor first?".to_string()], page_index: 0, awaiting: None } 
-                 } else if self.lamp_done { 
-                     DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Well good luck, if you’re fighting the Weeping, you’ll need it!".to_string()], page_index: 0, awaiting: None } 
-                 } else {
-                    let missing = if self.player.inventory.sword.is_none() { "Sword" } else { "Shield" };
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec![format!("Hey! Did Noor send you? Yeah, they’re a bit rough around the edges. So you’re missing a {}, well take this!", missing), format!("You got the {}.", missing)], page_index: 0, awaiting: None }
-                 }
-            },
-            NpcId::Dorosht => {
-                if self.dorosht_completed {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Thanks again!".to_string()], page_index: 0, awaiting: None }
-                } else if self.dorosht_accepted {
-                    if self.swap_dorosht_item() {
-                        self.dorosht_completed = true;
-                        DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec![
-                            "Well is that it! My goodness, I never thought you’d come back alive, let alone with the dagger! A deal’s a deal, here is the axe!".to_string()
-                        ], page_index: 0, awaiting: None }
-                    } else {
-                        DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Come back with the dagger, and she’s yours!".to_string()], page_index: 0, awaiting: None }
-                    }
-                } else {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Hey there mighty traveler, rumor is, you’re going to go into the Weeping Willow Woods… if you do, might you fetch me something? (Y/N)".to_string()], page_index: 0, awaiting: Some(AwaitingChoice::YesNoDorosht) }
-                }
-            },
-            NpcId::Random1 => {
-                if self.dorosht_completed && self.mah_defeated {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["The sunshine, but at what cost...".to_string()], page_index: 0, awaiting: None }
-                } else {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Isn’t it bad? So gloomy, so dark, I need some vitamin D pills or something!".to_string()], page_index: 0, awaiting: None }
-                }
-            },
-            NpcId::Random2 => {
-                if self.dorosht_completed && self.mah_defeated {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["I KNEW IT!".to_string()], page_index: 0, awaiting: None }
-                } else {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["I actually overheard the Mayor talking to himself, I think he’s going a bit cukoo!!".to_string()], page_index: 0, awaiting: None }
-                }
-            },
-            NpcId::Random3 => {
-                if self.dorosht_completed && self.mah_defeated {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["I'd rather pay taxes than go through what they have, for the sake they have...".to_string()], page_index: 0, awaiting: None }
-                } else {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Oh please, if you think the Weeping are bad, wait until you hear from the IRS!".to_string()], page_index: 0, awaiting: None }
-                }
-            },
-            
-            NpcId::Weeping1 => DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["I can’t believe that’s how they think of us in here, we literally get our name from the Weeping Willow trees that we LIVE in. Like come on!".to_string()], page_index: 0, awaiting: None },
-            NpcId::Weeping2 => DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["It sure is cold out, all that global warming bibble babble is a hoax!".to_string()], page_index: 0, awaiting: None },
-            NpcId::Weeping3 => DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Have you talked to the guy who thinks global warming is fake? What a nut!".to_string()], page_index: 0, awaiting: None },
-            NpcId::Weeping4 => DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["I had a friend in that village…".to_string(), "His name meant bright, just like how he was.".to_string(), "I wonder how he’s doing…".to_string()], page_index: 0, awaiting: None },
-
-            NpcId::Shab => {
-                if self.shab_defeated {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Get away from me, I’m training…".to_string()], page_index: 0, awaiting: None }
-                } else {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Hey! You’re not supposed to be in here, who are you?!".to_string(), "Wait, nevermind, I couldn’t care less, are you ready to die?!!!".to_string()], page_index: 0, awaiting: None }
-                }
-            },
-            NpcId::Krad => {
-                if self.krad_defeated {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["W-what do you want from me?!?!?!".to_string(), "LEAVE ME BE, you’ve shattered my honor, and my armor….".to_string(), " *sniffles* ".to_string()], page_index: 0, awaiting: None }
-                } else {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Who are you…".to_string(), "Doesn’t matter… my armor…".to_string(), "IS IMPENETRABLE".to_string()], page_index: 0, awaiting: None }
+        let Some(def) = npc::registry().get(&npc.id) else { return };
+        if let Some(branch) = def.dialogue.start.iter().find(|b| self.check_all(&b.when)) {
+            self.enter_node(&npc.id, npc.name.clone(), &branch.node);
+            self.update_quests(Some(&npc.id));
+        }
+    }
+
+    fn objective_met(&self, obj: &Objective, talked: Option<&NpcId>) -> bool {
+        match obj {
+            Objective::TalkTo(id) => talked == Some(id),
+            Objective::Defeat(id) => self.flag(&npc::defeated_flag(id)),
+            Objective::Fetch(id) => self.has_item(id),
+        }
+    }
+
+    /// Starts, advances and finishes quests against the current state.
+    /// `talked` is the NPC the player just spoke to, for talk-to objectives.
+    fn update_quests(&mut self, talked: Option<&NpcId>) {
+        for def in quest::registry().all() {
+            let mut p = match self.quests.get(&def.id) {
+                Some(p) if p.completed => continue,
+                Some(p) => p.clone(),
+                None if self.check_all(&def.start_when) => QuestProgress::default(),
+                None => continue,
+            };
+            let started = !self.quests.contains_key(&def.id);
+
+            if !def.done_when.is_empty() && self.check_all(&def.done_when) {
+                // Already finished before quests were tracked; rewards were handed out then.
+                p.stage = def.stages.len();
+                p.completed = true;
+                self.quests.insert(def.id.clone(), p);
+                continue;
+            }
+            if started { self.push_log(format!("New quest: {}", def.title)); }
+
+            loop {
+                let stage = &def.stages[p.stage];
+                p.objectives_done.resize(stage.objectives.len(), false);
+                for (done, obj) in p.objectives_done.iter_mut().zip(&stage.objectives) {
+                    *done = *done || self.objective_met(obj, talked);
                 }
-            },
-            NpcId::Mah => {
-                if self.shab_defeated && self.krad_defeated {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["You low-class scum".to_string(), "You come into my home, my community…".to_string(), "AND KILL MY MEN?!?!?!".to_string()], page_index: 0, awaiting: None }
-                } else {
-                    DialogueSession { npc: npc.id, title: npc.name.clone(), pages: vec!["Insignificant being, begone from my sight, my men will handle you…".to_string()], page_index: 0, awaiting: None }
+                if !p.objectives_done.iter().all(|d| *d) { break; }
+
+                for e in &stage.on_complete { self.apply_effect(e, None); }
+                p.stage += 1;
+                p.objectives_done.clear();
+                if p.stage == def.stages.len() {
+                    p.completed = true;
+                    for e in &def.rewards { self.apply_effect(e, None); }
+                    self.push_log(format!("Quest complete: {}", def.title));
+                    break;
                 }
+                self.push_log(format!("Quest updated: {}", def.title));
             }
-        };
-        self.dialogue = Some(session);
-        self.state = GameState::Dialogue;
+            self.quests.insert(def.id.clone(), p);
+        }
+    }
+
+    fn close_dialogue(&mut self) {
+        let Some(d) = self.dialogue.take() else { return };
+        self.state = if self.battle.is_some() { GameState::Battle } else { GameState::Playing };
+
+        if let (Some(npc), Some(node_id)) = (&d.npc, &d.node)
+            && let Some(node) = npc::registry().get(npc).and_then(|def| def.dialogue.node(node_id))
+        {
+            for e in &node.on_close { self.apply_effect(e, Some(npc)); }
+        }
     }
 
     fn dialogue_continue(&mut self) {
-        let mut start_battle_id = None;
         if let Some(d) = &mut self.dialogue {
             if d.page_index + 1 < d.pages.len() {
                 d.page_index += 1;
             } else {
-                match d.npc {
-                    NpcId::Shab if !self.shab_defeated => start_battle_id = Some(NpcId::Shab),
-                    NpcId::Krad if !self.krad_defeated => start_battle_id = Some(NpcId::Krad),
-                    NpcId::Mah if !self.mah_defeated && self.shab_defeated && self.krad_defeated => start_battle_id = Some(NpcId::Mah),
-                    NpcId::MayorSol if !self.mayor_defeated && self.dorosht_completed && self.mah_defeated => start_battle_id = Some(NpcId::MayorSol),
-                    NpcId::MayorSol if self.mayor_defeated => {
-                        self.state = GameState::Fin;
-                        self.dialogue = None;
-                        return;
-                    }
-                    _ => {}
-                }
-                self.dialogue = None;
-                self.state = GameState::Playing;
+                self.close_dialogue();
             }
         }
-        if let Some(id) = start_battle_id {
-            self.start_battle(id);
-        }
     }
 
     fn dialogue_choice(&mut self, c: char) {
@@ -919,44 +1110,20 @@ impl World {
         let up = c.to_ascii_uppercase();
 
         match awaiting {
-            Some(AwaitingChoice::YesNoMayor) => {
-                if up == 'Y' || up == 'N' {
-                    let yes = up == 'Y';
-                    self.mayor_done = true;
-                    if let Some(d) = &mut self.dialogue {
-                        d.awaiting = None;
-                        d.pages = vec![if yes { "Why thank you! Now go talk to Noor to get you started.".to_string() } else { "Aren’t you rude, I’ve been nothing but kind. Fine, go to Noor to get you started I guess…".to_string() }];
-                        d.page_index = 0;
-                    }
-                }
-            }
-            Some(AwaitingChoice::YesNoDorosht) => {
-                if up == 'Y' {
-                    self.dorosht_accepted = true;
-                    if let Some(d) = &mut self.dialogue {
-                        d.awaiting = None;
-                        d.pages = vec![
-                            "Do ya really mean it?! Why thank you mighty one! What I’m looking for is…. the Weeping dagger!".to_string(),
-                            "Legend states, it is a cursed blade, forged by the most brutal of the Weeping.".to_string(),
-                            "Now don’t you worry, you won’t trade for nothing, I have here is a mighty fine weapon, the Willow Axe, made with a fine steel, along with the strong bark of the Weeping Willow Forest.".to_string(),
-                            "Come back with the dagger, and she’s yours!".to_string(),
-                        ];
-                        d.page_index = 0;
-                    }
-                } else if up == 'N' {
-                    if let Some(d) = &mut self.dialogue {
-                        d.awaiting = None;
-                        d.pages = vec!["Well, I won’t be going anywhere in case you change your mind.".to_string()];
-                        d.page_index = 0;
-                    }
-                }
-            }
-            Some(AwaitingChoice::ABNoorWeapon) => {
-                if up == 'A' || up == 'B' {
-                    if up == 'A' { self.player.equip_sword(Equipment { name: "Basic Sword".to_string(), slot: Slot::Sword, hp_bonus: 0, atk_bonus: 3, def_bonus: 0, speed_bonus: 3 }); } 
-                    else { self.player.equip_shield(Equipment { name: "Basic Shield".to_string(), slot: Slot::Shield, hp_bonus: 0, atk_bonus: 0, def_bonus: 3, speed_bonus: -2 }); }
-                    self.noor_done = true;
-                    if let Some(d) = &mut self.dialogue { d.awaiting = None; d.page_index = 2; }
+            Some(AwaitingChoice::Choices) => {
+                let Some(d) = &self.dialogue else { return };
+                // Choices are only offered once the last page is on screen.
+                if d.page_index + 1 < d.pages.len() { return; }
+                let (Some(npc), Some(node_id)) = (d.npc.clone(), d.node.clone()) else { return };
+                let title = d.title.clone();
+
+                let Some(node) = npc::registry().get(&npc).and_then(|def| def.dialogue.node(&node_id)) else { return };
+                let Some(choice) = node.choices.iter().find(|ch| ch.key == up && self.check_all(&ch.when)) else { return };
+
+                for e in &choice.effects { self.apply_effect(e, Some(&npc)); }
+                match &choice.next {
+                    Some(next) => self.enter_node(&npc, title, next),
+                    None => { self.dialogue = None; self.state = GameState::Playing; }
                 }
             }
             Some(AwaitingChoice::Chest { item, weapon, .. }) => {
@@ -964,21 +1131,19 @@ impl World {
                 match up {
                     'A' => {
                         if let Some(w) = weapon {
-                            self.player.inventory.backpack.push(w.clone());
+                            self.player.inventory.backpack.push(w.as_ref().clone());
                             log = Some(format!("Picked up {}.", w.name));
                         } else if let Some(cons) = item {
-                            if self.player.inventory.consumables.len() < 10 {
-                                self.player.inventory.consumables.push(cons.clone());
-                                log = Some(format!("Picked up {}.", cons.name));
-                            } else { log = Some("Slots full.".to_string()); }
+                            if !self.player.inventory.can_hold(&cons) {
+                                return self.start_swap_dialogue(cons, 0);
+                            }
+                            log = Some(format!("Picked up {}.", cons.name));
+                            self.player.inventory.add_consumable(cons);
                         }
                     }
                     'B' => {
                         if let Some(cons) = item {
-                            let before = self.player.hp;
-                            self.player.hp = (self.player.hp + cons.heal).min(self.player.max_hp);
-                            let healed = self.player.hp - before;
-                            log = Some(format!("Used {} ({}).", cons.name, Self::fmt_hp_delta(healed)));
+                            log = Some(self.consume(&cons));
                         } else { log = Some("Cannot use that.".to_string()); }
                     }
                     'C' => { log = Some("Left chest.".to_string()); }
@@ -988,6 +1153,33 @@ impl World {
                 self.dialogue = None;
                 self.state = GameState::Playing;
             }
+            Some(AwaitingChoice::Swap { item, page }) => {
+                let singles = self.swappable();
+                let log = match up {
+                    'Z' => {
+                        let log = format!("Left the {} in the chest.", item.name);
+                        self.restock_chest(item);
+                        log
+                    }
+                    'N' if singles.len() > SWAP_PAGE => {
+                        let pages = singles.len().div_ceil(SWAP_PAGE);
+                        return self.start_swap_dialogue(item, (page + 1) % pages);
+                    }
+                    'A'..='D' => {
+                        let Some(&i) = singles.get(page * SWAP_PAGE + (up as usize - 'A' as usize)) else { return };
+                        let inv = &mut self.player.inventory;
+                        let old = inv.take_consumable(i);
+                        let log = format!("Swapped {} for {}; it's back in the chest.", old.name, item.name);
+                        inv.add_consumable(item);
+                        self.restock_chest(old);
+                        log
+                    }
+                    _ => return,
+                };
+                self.push_log(log);
+                self.dialogue = None;
+                self.state = GameState::Playing;
+            }
             None => {}
         }
     }
@@ -1010,75 +1202,111 @@ impl World {
     }
 
     pub fn apply_action(&mut self, action: Action) -> bool {
-        self.player.purge_expired_buffs();
+        let running = self.handle_action(action);
+        if !matches!(self.state, GameState::Title | GameState::Intro) { self.update_quests(None); }
+        running
+    }
+
+    fn handle_action(&mut self, action: Action) -> bool {
         match self.state {
             GameState::Title => match action { Action::Confirm => self.state = GameState::Intro, Action::Quit => return false, _ => {} },
             GameState::Intro => match action { Action::Confirm => self.state = GameState::Playing, Action::Quit => return false, _ => {} },
             GameState::Dialogue => match action { Action::Confirm => self.dialogue_continue(), Action::Choice(c) => self.dialogue_choice(c), Action::Quit => return false, _ => {} },
-            GameState::Fin => match action { Action::Quit => return false, _ => {} },
+            GameState::Fin => if matches!(action, Action::Quit) { return false },
             
+            GameState::Battle if self.battle.as_ref().is_some_and(|b| b.pending.is_some()) => match action {
+                Action::Confirm => self.confirm_target(),
+                Action::Cancel => self.cancel_target(),
+                Action::InventoryUp => self.move_target(-1),
+                Action::InventoryDown => self.move_target(1),
+                Action::Quit => return false,
+                _ => {}
+            }
+
             GameState::Battle => match action {
                 Action::BattleOption(opt, penalty) => {
                     if opt == 1 || opt == 3 {
-                        if opt == 1 && !penalty && self.battle.as_ref().map_or(false, |b| b.enemy_speed < self.player.speed()) {
-                            if let Some(bs) = &mut self.battle { bs.player_initiated = true; }
+                        let speed = self.player.speed();
+                        if opt == 1 && !penalty && self.battle.as_ref().is_some_and(|b| b.enemies.iter().all(|e| e.is_down() || e.speed < speed))
+                            && let Some(bs) = &mut self.battle
+                        {
+                            bs.player_initiated = true;
                         }
-                        self.apply_battle_turn(opt, penalty);
+                        if opt == 1 && self.choose_target(PendingAttack::Fight) {
+                            if let Some(bs) = &mut self.battle { bs.penalty_mode |= penalty; }
+                            return true;
+                        }
+                        let choice = if opt == 1 { TurnChoice::Fight } else { TurnChoice::Flee };
+                        self.apply_battle_turn(choice, penalty);
                     } else if opt == 2 {
                         self.inventory_open = true;
                         self.player.inventory.tab = InvTab::Consumables;
+                        if let Some(bs) = &mut self.battle { bs.skills_open = false; }
+                    } else if opt == 4 {
+                        self.toggle_skills();
                     }
                 }
-                Action::UseConsumable => {
-                    if self.inventory_open {
-                        self.use_or_unequip_or_equip();
-                        self.inventory_open = false;
-                        self.apply_battle_turn(2, false);
-                    }
+                Action::UseSkill if self.battle.as_ref().is_some_and(|b| b.skills_open) => self.use_selected_skill(),
+                Action::UseConsumable if self.inventory_open => {
+                    self.use_or_unequip_or_equip();
+                    self.inventory_open = false;
+                    self.apply_battle_turn(TurnChoice::UsedItem, false);
                 }
                 Action::ToggleInventory | Action::Quit => {
                     if self.inventory_open { self.inventory_open = false; }
                     else if matches!(action, Action::Quit) { return false; }
                 }
-                Action::InventoryUp => { if self.inventory_open { self.player.inventory.move_cursor(-1); } }
-                Action::InventoryDown => { if self.inventory_open { self.player.inventory.move_cursor(1); } }
+                Action::InventoryUp => {
+                    if self.inventory_open { self.player.inventory.move_cursor(-1); } else { self.move_skill_cursor(-1); }
+                }
+                Action::InventoryDown => {
+                    if self.inventory_open { self.player.inventory.move_cursor(1); } else { self.move_skill_cursor(1); }
+                }
                 _ => {}
             }
 
+            GameState::Playing if self.shop.is_some() => match action {
+                Action::InventoryUp => self.move_shop_cursor(-1),
+                Action::InventoryDown => self.move_shop_cursor(1),
+                Action::ToggleInvTab if self.shop.as_ref().is_some_and(|s| s.mode == ShopMode::Sell) => self.toggle_inventory_tab(),
+                Action::UseConsumable => self.shop_confirm(),
+                Action::ToggleInventory | Action::Cancel => self.shop = None,
+                Action::Quit => return false,
+                _ => {}
+            },
+
             GameState::Playing => match action {
                 Action::ToggleStats => self.toggle_stats(),
                 Action::ToggleInventory => self.toggle_inventory(),
-                Action::ToggleInvTab => if self.inventory_open { self.toggle_inventory_tab() },
-                Action::InventoryUp => if self.inventory_open { self.player.inventory.move_cursor(-1) },
-                Action::InventoryDown => if self.inventory_open { self.player.inventory.move_cursor(1) },
-                Action::UseConsumable => if self.inventory_open { self.use_or_unequip_or_equip() },
+                Action::ToggleJournal => self.toggle_journal(),
+                Action::ToggleInvTab if self.inventory_open => self.toggle_inventory_tab(),
+                Action::InventoryUp => if self.inventory_open { self.player.inventory.move_cursor(-1) } else if self.stats_open { self.move_stat_cursor(-1) },
+                Action::InventoryDown => if self.inventory_open { self.player.inventory.move_cursor(1) } else if self.stats_open { self.move_stat_cursor(1) },
+                Action::UseConsumable if self.inventory_open => self.use_or_unequip_or_equip(),
+                Action::SpendPoint if self.stats_open => self.spend_stat_point(),
                 Action::Interact => {
                     if let Some(npc) = self.npc_near_player().cloned() {
                         self.start_dialogue_for(&npc);
-                        if self.noor_done && npc.id == NpcId::Lamp && !self.lamp_done {
-                            let ms = self.player.inventory.sword.is_none();
-                            let msh = self.player.inventory.shield.is_none();
-                            if ms { self.player.equip_sword(Equipment { name: "Basic Sword".to_string(), slot: Slot::Sword, hp_bonus: 0, atk_bonus: 3, def_bonus: 0, speed_bonus: 3 }); self.lamp_done = true; }
-                            else if msh { self.player.equip_shield(Equipment { name: "Basic Shield".to_string(), slot: Slot::Shield, hp_bonus: 0, atk_bonus: 0, def_bonus: 3, speed_bonus: -2 }); self.lamp_done = true; }
+                    } else if self.door_near_player().is_some() {
+                        match door::registry().rule(self.current) {
+                            Some(rule) if !self.check_all(&rule.requires) => self.push_log(self.locked_text(rule)),
+                            _ => self.toggle_room(),
                         }
                     } else {
-                        if let Some(_) = self.door_near_player() {
-                             if self.player.inventory.sword.is_some() && self.player.inventory.shield.is_some() { self.toggle_room(); } 
-                             else { self.push_log("Talk to the mayor and come back"); }
-                        } else {
-                             self.open_chest_if_on_one();
-                             if self.state != GameState::Dialogue { self.push_log("No one nearby."); }
-                        }
+                        self.open_chest_if_on_one();
+                        if self.state != GameState::Dialogue { self.push_log("No one nearby."); }
                     }
                 }
                 Action::Move(dx, dy) => {
-                    if self.inventory_open || self.stats_open { return true; }
+                    if self.inventory_open || self.stats_open || self.journal_open { return true; }
                     let nx = self.player.x + dx;
                     let ny = self.player.y + dy;
                     if self.npc_at(self.current, nx, ny).is_some() { return true; }
                     let map_snap = self.current_map().clone();
+                    let oldp = (self.player.x, self.player.y);
                     self.player.try_move(dx, dy, &map_snap);
                     let newp = (self.player.x, self.player.y);
+                    if newp != oldp { self.tick_buffs(); }
                     if self.current_map().get(newp.0 as usize, newp.1 as usize) == Tile::Chest { self.open_chest_if_on_one(); }
                 }
                 Action::Quit => return false,
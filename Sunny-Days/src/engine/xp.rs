@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+/// Stat points handed out for every level gained.
+pub const POINTS_PER_LEVEL: u32 = 3;
+
+/// XP needed to go from `level` to the next one.
+pub fn to_next(level: u32) -> u32 {
+    15 + 10 * (level - 1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Experience {
+    pub level: u32,
+    pub xp: u32,     // toward the next level
+    pub points: u32, // stat points not spent yet
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self { level: 1, xp: 0, points: 0 }
+    }
+}
+
+impl Experience {
+    /// Adds XP, levelling up as many times as it covers. Returns the levels gained.
+    pub fn gain(&mut self, xp: u32) -> u32 {
+        self.xp += xp;
+        let mut gained = 0;
+        while self.xp >= to_next(self.level) {
+            self.xp -= to_next(self.level);
+            self.level += 1;
+            self.points += POINTS_PER_LEVEL;
+            gained += 1;
+        }
+        gained
+    }
+}
+
+/// Where a stat point can go, in the order the level-up screen lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatChoice {
+    Attack,
+    Defense,
+    Speed,
+    MaxHp,
+}
+
+impl StatChoice {
+    pub const ALL: [StatChoice; 4] = [StatChoice::Attack, StatChoice::Defense, StatChoice::Speed, StatChoice::MaxHp];
+
+    /// How much one point raises the stat.
+    pub fn per_point(self) -> i32 {
+        match self {
+            StatChoice::Attack | StatChoice::Defense | StatChoice::Speed => 3,
+            StatChoice::MaxHp => 5,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StatChoice::Attack => "ATK",
+            StatChoice::Defense => "DEF",
+            StatChoice::Speed => "SPD",
+            StatChoice::MaxHp => "Max HP",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn big_rewards_can_skip_levels() {
+        let mut exp = Experience::default();
+        assert_eq!(exp.gain(10), 0);
+        assert_eq!(exp.gain(35), 2); // 15 to reach level 2, 25 more for level 3
+        assert_eq!(exp, Experience { level: 3, xp: 5, points: 2 * POINTS_PER_LEVEL });
+    }
+}
@@ -0,0 +1,7 @@
+//! Sunny Day(s): the game engine, map generation and terminal front end.
+//! `engine::headless` runs the game without a terminal, for tests and tools.
+
+pub mod audio;
+pub mod engine;
+pub mod map;
+pub mod tui;
@@ -1,10 +1,36 @@
-mod engine;
-mod map;
-mod tui;
-mod audio;
+use sunny_days::engine::game_loop::{run, RunOptions};
 
-use engine::game_loop::run;
+use std::path::PathBuf;
 
-fn main() -> std::io::Result<()> {
-    run()
+const USAGE: &str = "usage: Sunny-Days [--replay <file> [--speed <x>]] [--record <file>]";
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<RunOptions, String> {
+    let mut opts = RunOptions::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--replay" => opts.replay = Some(PathBuf::from(value()?)),
+            "--record" => opts.record = Some(PathBuf::from(value()?)),
+            "--speed" => {
+                let raw = value()?;
+                opts.speed = raw.parse().map_err(|_| format!("bad speed `{raw}`"))?;
+            }
+            _ => return Err(format!("unknown argument `{arg}`")),
+        }
+    }
+    Ok(opts)
+}
+
+fn main() {
+    let opts = match parse_args(std::env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(opts) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
 }
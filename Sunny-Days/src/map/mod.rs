@@ -1,9 +1,10 @@
 pub mod generator;
 pub mod tile;
 
+use serde::{Deserialize, Serialize};
 use tile::Tile;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Map {
     pub width: usize,
     pub height: usize,
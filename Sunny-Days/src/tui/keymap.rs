@@ -0,0 +1,462 @@
+use crate::engine::action::Action;
+use crate::engine::npc;
+use crate::engine::save::SaveSlot;
+use crate::engine::world::{GameState, World};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+// Defaults ship with the game; players override them in `keys.json` next to Cargo.toml.
+const DEFAULT_KEYS: &str = include_str!("../../assets/data/keys.json");
+const USER_KEYS: &str = "keys.json";
+
+/// Which set of bindings is live, from the game state and open panels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Context {
+    Global, // checked first, whatever else is open
+    Title,  // also the intro screen
+    Playing,
+    Inventory,
+    Stats,
+    Journal,
+    Shop,
+    Dialogue,
+    Battle,
+    BattleInventory,
+    BattleSkills,
+    BattleTarget,
+    Fin,
+}
+
+impl Context {
+    pub fn of(world: &World) -> Self {
+        match world.state {
+            GameState::Title | GameState::Intro => Context::Title,
+            GameState::Dialogue => Context::Dialogue,
+            GameState::Fin => Context::Fin,
+            GameState::Battle if world.inventory_open => Context::BattleInventory,
+            GameState::Battle if world.battle.as_ref().is_some_and(|b| b.pending.is_some()) => Context::BattleTarget,
+            GameState::Battle if world.battle.as_ref().is_some_and(|b| b.skills_open) => Context::BattleSkills,
+            GameState::Battle => Context::Battle,
+            GameState::Playing if world.shop.is_some() => Context::Shop,
+            GameState::Playing if world.stats_open => Context::Stats,
+            GameState::Playing if world.journal_open => Context::Journal,
+            GameState::Playing if world.inventory_open => Context::Inventory,
+            GameState::Playing => Context::Playing,
+        }
+    }
+
+    /// Keys this context keeps for itself, and what they're for.
+    fn reserved(self) -> (Vec<Key>, &'static str) {
+        match self {
+            // 1-3 load a slot, A loads the autosave
+            Context::Title => (vec![Key::Char('1'), Key::Char('2'), Key::Char('3'), Key::Char('a')], "save slots"),
+            // The chest's options and swap list (A-D, N for more, Z to leave),
+            // plus every letter an NPC's choices use
+            Context::Dialogue => {
+                let npcs = npc::registry().all().iter().flat_map(|d| d.dialogue.choice_keys());
+                let keys = "abcdnz".chars().chain(npcs).map(|c| Key::Char(c.to_ascii_lowercase())).collect();
+                (keys, "dialogue choices")
+            }
+            _ => (Vec::new(), ""),
+        }
+    }
+}
+
+/// Something a key can be bound to. Declaration order is the order the help lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    MoveUp,
+    MoveLeft,
+    MoveDown,
+    MoveRight,
+    Interact,
+    Confirm,
+    CursorUp,
+    CursorDown,
+    InventoryTab,
+    UseItem,
+    Inventory,
+    Stats,
+    Journal,
+    Fight,
+    BattleItem,
+    Flee,
+    Skills,
+    UseSkill,
+    Cancel,
+    SpendPoint,
+    Save, // to the active slot
+    #[serde(rename = "save_slot_1")]
+    SaveSlot1,
+    #[serde(rename = "save_slot_2")]
+    SaveSlot2,
+    #[serde(rename = "save_slot_3")]
+    SaveSlot3,
+    Quit,
+}
+
+impl Command {
+    /// `penalty` is the battle loop's 10s idle penalty, only used by battle options.
+    pub fn action(self, penalty: bool) -> Action {
+        match self {
+            Command::MoveUp => Action::Move(0, -1),
+            Command::MoveLeft => Action::Move(-1, 0),
+            Command::MoveDown => Action::Move(0, 1),
+            Command::MoveRight => Action::Move(1, 0),
+            Command::Interact => Action::Interact,
+            Command::Confirm => Action::Confirm,
+            Command::CursorUp => Action::InventoryUp,
+            Command::CursorDown => Action::InventoryDown,
+            Command::InventoryTab => Action::ToggleInvTab,
+            Command::UseItem => Action::UseConsumable,
+            Command::Inventory => Action::ToggleInventory,
+            Command::Stats => Action::ToggleStats,
+            Command::Journal => Action::ToggleJournal,
+            Command::Fight => Action::BattleOption(1, penalty),
+            Command::BattleItem => Action::BattleOption(2, penalty),
+            Command::Flee => Action::BattleOption(3, penalty),
+            Command::Skills => Action::BattleOption(4, penalty),
+            Command::UseSkill => Action::UseSkill,
+            Command::Cancel => Action::Cancel,
+            Command::SpendPoint => Action::SpendPoint,
+            // The game loop writes saves itself; see `save_slot`.
+            Command::Save | Command::SaveSlot1 | Command::SaveSlot2 | Command::SaveSlot3 => Action::None,
+            Command::Quit => Action::Quit,
+        }
+    }
+
+    /// The slot a save command writes to, given the one the run is using.
+    pub fn save_slot(self, active: SaveSlot) -> Option<SaveSlot> {
+        match self {
+            Command::Save => Some(active),
+            Command::SaveSlot1 => Some(SaveSlot::Manual(1)),
+            Command::SaveSlot2 => Some(SaveSlot::Manual(2)),
+            Command::SaveSlot3 => Some(SaveSlot::Manual(3)),
+            _ => None,
+        }
+    }
+
+    pub fn is_battle_option(self) -> bool {
+        matches!(self, Command::Fight | Command::BattleItem | Command::Flee | Command::Skills)
+    }
+
+    /// Help text; commands with the same label share a line.
+    fn label(self, ctx: Context) -> &'static str {
+        let closes = |own| ctx == own || (own == Context::Inventory && matches!(ctx, Context::BattleInventory | Context::Shop));
+        match self {
+            Command::MoveUp | Command::MoveLeft | Command::MoveDown | Command::MoveRight => "Move",
+            Command::Interact => "Talk / Open chest",
+            Command::Confirm if ctx == Context::BattleTarget => "Attack",
+            Command::Confirm => "Continue",
+            Command::CursorUp | Command::CursorDown => "Select",
+            Command::InventoryTab => "Change tab",
+            Command::UseItem if ctx == Context::Shop => "Buy / sell",
+            Command::UseItem => "Use / unequip / equip",
+            Command::Inventory if closes(Context::Inventory) => "Close",
+            Command::Inventory => "Inventory",
+            Command::Stats if closes(Context::Stats) => "Close",
+            Command::Stats => "Stats",
+            Command::Journal if closes(Context::Journal) => "Close",
+            Command::Journal => "Journal",
+            Command::Fight => "Fight",
+            Command::BattleItem => "Inventory",
+            Command::Flee => "Run",
+            Command::Skills if ctx == Context::BattleSkills => "Close",
+            Command::Skills => "Skills",
+            Command::UseSkill => "Use skill",
+            Command::Cancel => "Back",
+            Command::SpendPoint => "Spend point",
+            Command::Save | Command::SaveSlot1 | Command::SaveSlot2 | Command::SaveSlot3 => "Save",
+            Command::Quit => "Quit",
+        }
+    }
+}
+
+/// A bindable key, written in config as a single character or a name like
+/// "Esc", "F1" or "Ctrl+S". Letters are case-insensitive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Key {
+    Char(char),
+    Space,
+    Enter,
+    Esc,
+    Tab,
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+    F(u8),
+    Ctrl(char),
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        let mut chars = raw.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return match c {
+                ' ' => Ok(Key::Space),
+                c if c.is_ascii_graphic() => Ok(Key::Char(c.to_ascii_lowercase())),
+                _ => Err(format!("unsupported key `{raw}`")),
+            };
+        }
+        match raw.as_str() {
+            "Space" => Ok(Key::Space),
+            "Enter" => Ok(Key::Enter),
+            "Esc" => Ok(Key::Esc),
+            "Tab" => Ok(Key::Tab),
+            "Backspace" => Ok(Key::Backspace),
+            "Up" => Ok(Key::Up),
+            "Down" => Ok(Key::Down),
+            "Left" => Ok(Key::Left),
+            "Right" => Ok(Key::Right),
+            _ => {
+                if let Some(c) = raw.strip_prefix("Ctrl+").and_then(|k| k.parse::<char>().ok()).filter(char::is_ascii_alphabetic) {
+                    return Ok(Key::Ctrl(c.to_ascii_lowercase()));
+                }
+                match raw.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => Ok(Key::F(n)),
+                    _ => Err(format!("unknown key `{raw}`")),
+                }
+            }
+        }
+    }
+}
+
+impl Key {
+    fn from_event(key: &KeyEvent) -> Option<Self> {
+        Some(match key.code {
+            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => Key::Ctrl(c.to_ascii_lowercase()),
+            KeyCode::Char(' ') => Key::Space,
+            KeyCode::Char(c) => Key::Char(c.to_ascii_lowercase()),
+            KeyCode::Enter => Key::Enter,
+            KeyCode::Esc => Key::Esc,
+            KeyCode::Tab => Key::Tab,
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::Up => Key::Up,
+            KeyCode::Down => Key::Down,
+            KeyCode::Left => Key::Left,
+            KeyCode::Right => Key::Right,
+            KeyCode::F(n) => Key::F(n),
+            _ => return None,
+        })
+    }
+
+    fn is_arrow(self) -> bool {
+        matches!(self, Key::Up | Key::Down | Key::Left | Key::Right)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            Key::Ctrl(c) => write!(f, "Ctrl+{}", c.to_ascii_uppercase()),
+            Key::F(n) => write!(f, "F{n}"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+type Bindings = BTreeMap<Context, BTreeMap<Command, Vec<Key>>>;
+
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Bindings,
+}
+
+impl KeyMap {
+    pub fn defaults() -> Self {
+        let bindings: Bindings = serde_json::from_str(DEFAULT_KEYS).expect("built-in keys.json parses");
+        let map = Self { bindings };
+        map.validate().expect("built-in keys.json has no conflicts");
+        map
+    }
+
+    /// Defaults with the commands named in `json` rebound. Within a context,
+    /// each listed command's keys replace its default keys; others stay put.
+    pub fn with_overrides(json: &str) -> Result<Self, Box<dyn Error>> {
+        let overrides: Bindings = serde_json::from_str(json)?;
+        let mut map = Self::defaults();
+        for (ctx, commands) in overrides {
+            map.bindings.entry(ctx).or_default().extend(commands);
+        }
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// The player's `keys.json` if there is one, otherwise the defaults.
+    /// Returns a warning to show when the file exists but can't be used.
+    pub fn load() -> (Self, Option<String>) {
+        let path = user_path();
+        match fs::read_to_string(&path) {
+            Err(_) => (Self::defaults(), None),
+            Ok(json) => match Self::with_overrides(&json) {
+                Ok(map) => (map, None),
+                Err(e) => (Self::defaults(), Some(format!("{}: {e}; using default keys", path.display()))),
+            },
+        }
+    }
+
+    /// Rejects a key bound twice in one context, or a key the loop already uses there.
+    fn validate(&self) -> Result<(), String> {
+        for (ctx, commands) in &self.bindings {
+            let mut seen: Vec<(Key, Command)> = Vec::new();
+            let (reserved, why) = ctx.reserved();
+            for (&cmd, keys) in commands {
+                for &key in keys {
+                    if let Some((_, other)) = seen.iter().find(|(k, _)| *k == key) {
+                        return Err(format!("{ctx:?}: `{key}` is bound to both {other:?} and {cmd:?}"));
+                    }
+                    if reserved.contains(&key) {
+                        return Err(format!("{ctx:?}: `{key}` is reserved ({why})"));
+                    }
+                    if *ctx != Context::Global
+                        && let Some(global) = self.lookup_key(Context::Global, key)
+                    {
+                        return Err(format!("{ctx:?}: `{key}` is already Global {global:?}"));
+                    }
+                    seen.push((key, cmd));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn lookup(&self, ctx: Context, key: &KeyEvent) -> Option<Command> {
+        self.lookup_key(ctx, Key::from_event(key)?)
+    }
+
+    fn lookup_key(&self, ctx: Context, key: Key) -> Option<Command> {
+        self.bindings.get(&ctx)?
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(&cmd, _)| cmd)
+    }
+
+    /// Keys bound to a command, for hints like "E on +".
+    pub fn keys_for(&self, ctx: Context, cmd: Command) -> &[Key] {
+        self.bindings.get(&ctx).and_then(|c| c.get(&cmd)).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// "I / Esc": the keys bound to a command, for hints inside other text.
+    pub fn keys_label(&self, ctx: Context, cmd: Command) -> String {
+        match self.keys_for(ctx, cmd) {
+            [] => "(unbound)".to_string(),
+            keys => describe(&[keys.to_vec()]),
+        }
+    }
+
+    /// "Keys: label" lines for a context, in command order.
+    pub fn help(&self, ctx: Context) -> Vec<String> {
+        let mut groups: Vec<(&str, Vec<Vec<Key>>)> = Vec::new();
+        for (&cmd, keys) in self.bindings.get(&ctx).into_iter().flatten() {
+            if keys.is_empty() { continue; }
+            let label = cmd.label(ctx);
+            match groups.iter_mut().find(|(l, _)| *l == label) {
+                Some((_, g)) => g.push(keys.clone()),
+                None => groups.push((label, vec![keys.clone()])),
+            }
+        }
+        groups.into_iter().map(|(label, keys)| format!("{}: {}", describe(&keys), label)).collect()
+    }
+}
+
+/// Renders a group's keys: single letters of a shared group run together
+/// ("WASD"), and a full set of arrow keys collapses to "Arrows".
+fn describe(group: &[Vec<Key>]) -> String {
+    let all: Vec<Key> = group.iter().flatten().copied().collect();
+    let mut parts = Vec::new();
+
+    let letters: Vec<Key> = all.iter().copied().filter(|k| matches!(k, Key::Char(_))).collect();
+    if group.len() > 1 && !letters.is_empty() {
+        parts.push(letters.iter().map(Key::to_string).collect::<String>());
+    } else {
+        parts.extend(letters.iter().map(Key::to_string));
+    }
+
+    let named: Vec<Key> = all.iter().copied().filter(|k| !matches!(k, Key::Char(_))).collect();
+    let arrows = named.iter().filter(|k| k.is_arrow()).count();
+    if arrows == 4 {
+        parts.extend(named.iter().filter(|k| !k.is_arrow()).map(Key::to_string));
+        parts.push("Arrows".to_string());
+    } else {
+        parts.extend(named.iter().map(Key::to_string));
+    }
+    parts.join(" / ")
+}
+
+fn user_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(USER_KEYS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn defaults_match_the_old_hardcoded_keys() {
+        let keys = KeyMap::defaults();
+        assert_eq!(keys.lookup(Context::Playing, &press(KeyCode::Char('Q'))), Some(Command::Stats));
+        assert_eq!(keys.lookup(Context::Fin, &press(KeyCode::Char('q'))), Some(Command::Quit));
+        assert_eq!(keys.lookup(Context::Inventory, &press(KeyCode::Char('w'))), Some(Command::CursorUp));
+        assert_eq!(keys.lookup(Context::Dialogue, &press(KeyCode::Char('y'))), None);
+        assert_eq!(keys.help(Context::Playing)[0], "WASD / Arrows: Move");
+    }
+
+    #[test]
+    fn overrides_replace_only_the_named_commands() {
+        let keys = KeyMap::with_overrides(r#"{ "playing": { "stats": ["c"] } }"#).unwrap();
+        assert_eq!(keys.lookup(Context::Playing, &press(KeyCode::Char('c'))), Some(Command::Stats));
+        assert_eq!(keys.lookup(Context::Playing, &press(KeyCode::Char('q'))), None);
+        assert_eq!(keys.lookup(Context::Playing, &press(KeyCode::Char('i'))), Some(Command::Inventory));
+    }
+
+    #[test]
+    fn conflicts_are_rejected() {
+        let err = KeyMap::with_overrides(r#"{ "playing": { "journal": ["e"] } }"#).unwrap_err();
+        assert!(err.to_string().contains("`E` is bound to both Interact and Journal"), "{err}");
+
+        let err = KeyMap::with_overrides(r#"{ "title": { "confirm": ["2"] } }"#).unwrap_err();
+        assert!(err.to_string().contains("reserved"), "{err}");
+
+        let err = KeyMap::with_overrides(r#"{ "dialogue": { "confirm": ["d"] } }"#).unwrap_err();
+        assert!(err.to_string().contains("reserved (dialogue choices)"), "{err}");
+
+        // The merchant's Sell choice
+        let err = KeyMap::with_overrides(r#"{ "dialogue": { "confirm": ["s"] } }"#).unwrap_err();
+        assert!(err.to_string().contains("reserved (dialogue choices)"), "{err}");
+    }
+
+    #[test]
+    fn hints_follow_rebound_chords() {
+        let keys = KeyMap::with_overrides(r#"{ "global": { "quit": ["Ctrl+x"] }, "inventory": { "use_item": ["Enter"] } }"#).unwrap();
+        let ctrl_x = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(keys.lookup(Context::Global, &ctrl_x), Some(Command::Quit));
+        assert_eq!(keys.lookup(Context::Global, &press(KeyCode::F(2))), Some(Command::SaveSlot2));
+        assert_eq!(keys.lookup(Context::Playing, &ctrl_x), None);
+        assert_eq!(keys.keys_label(Context::Inventory, Command::UseItem), "Enter");
+        assert_eq!(keys.help(Context::Global), ["Ctrl+S / F1 / F2 / F3: Save", "Ctrl+X: Quit"]);
+
+        let err = KeyMap::with_overrides(r#"{ "playing": { "journal": ["F1"] } }"#).unwrap_err();
+        assert!(err.to_string().contains("Global"), "{err}");
+    }
+}
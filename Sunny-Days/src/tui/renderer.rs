@@ -1,5 +1,15 @@
-use crate::engine::world::{World, GameState, NpcId};
-use crate::engine::entity::{InvTab, InvSelection};
+use crate::engine::item::{self, ItemId};
+use crate::engine::npc::{self, NpcColor};
+use crate::engine::quest::Objective;
+use crate::engine::world::{World, GameState};
+use crate::engine::entity::{Equipment, GearSlot, InvTab, InvSelection};
+use crate::engine::rarity::Rarity;
+use crate::engine::save::{SaveSlot, SlotInfo};
+use crate::engine::shop::ShopMode;
+use crate::engine::stats::{Stat, Stats};
+use crate::engine::status::{self, StatusEffect};
+use crate::engine::xp::{self, StatChoice};
+use crate::tui::keymap::{Command, Context, KeyMap};
 use crate::map::tile::Tile;
 
 use ratatui::{
@@ -38,7 +48,23 @@ fn fmt_bonus(v: i32) -> String {
     if v >= 0 { format!("+{}", v) } else { format!("{}", v) }
 }
 
-pub fn render(f: &mut Frame, world: &World) {
+/// Unix seconds -> "YYYY-MM-DD HH:MM UTC" (civil-from-days, no date crate needed).
+fn fmt_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", y, m, d, rem / 3_600, (rem % 3_600) / 60)
+}
+
+pub fn render(f: &mut Frame, world: &World, slots: &[SlotInfo], keys: &KeyMap) {
     let size = f.size();
     f.render_widget(Clear, size);
 
@@ -51,16 +77,16 @@ pub fn render(f: &mut Frame, world: &World) {
     }
 
     match world.state {
-        GameState::Title => draw_title(f, size),
+        GameState::Title => draw_title(f, size, slots),
         GameState::Intro => draw_intro_static(f, size, world),
-        GameState::Playing | GameState::Dialogue => draw_playing(f, size, world),
-        GameState::Battle => draw_battle(f, size, world),
-        GameState::Fin => draw_fin(f, size),
+        GameState::Playing | GameState::Dialogue => draw_playing(f, size, world, keys),
+        GameState::Battle => draw_battle(f, size, world, keys),
+        GameState::Fin => draw_fin(f, size, keys),
     }
 }
 
-fn draw_title(f: &mut Frame, area: Rect) {
-    let lines = vec![
+fn draw_title(f: &mut Frame, area: Rect, slots: &[SlotInfo]) {
+    let mut lines = vec![
         Line::from(Span::styled(
             "Sunny Day",
             Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
@@ -71,9 +97,43 @@ fn draw_title(f: &mut Frame, area: Rect) {
             Style::default().fg(Color::White),
         )),
         Line::from(""),
-        Line::from("Click space to continue"),
+        Line::from("Click space to start a new game"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Continue",
+            Style::default().fg(Color::Cyan),
+        )),
     ];
 
+    for info in slots {
+        let key = match info.slot {
+            SaveSlot::Manual(n) => n.to_string(),
+            SaveSlot::Autosave => "A".to_string(),
+        };
+        let line = match (&info.meta, &info.error) {
+            (Some(m), _) => Line::from(format!(
+                "{}. {} - {} | Seed {} | HP {}/{} | Room {} | {}",
+                key,
+                info.slot.label(),
+                fmt_timestamp(m.saved_at),
+                m.seed,
+                m.hp,
+                m.max_hp,
+                m.room + 1,
+                m.progress,
+            )),
+            (None, Some(_)) => Line::from(Span::styled(
+                format!("{}. {} - <unreadable>", key, info.slot.label()),
+                Style::default().fg(Color::Red),
+            )),
+            (None, None) => Line::from(Span::styled(
+                format!("{}. {} - <empty>", key, info.slot.label()),
+                Style::default().fg(Color::DarkGray),
+            )),
+        };
+        lines.push(line);
+    }
+
     let title = Paragraph::new(lines)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::ALL));
@@ -102,7 +162,7 @@ fn draw_intro_static(f: &mut Frame, area: Rect, world: &World) {
     f.render_widget(intro, area);
 }
 
-fn draw_fin(f: &mut Frame, area: Rect) {
+fn draw_fin(f: &mut Frame, area: Rect, keys: &KeyMap) {
     let lines = vec![
         Line::from(""),
         Line::from(Span::styled(
@@ -120,7 +180,7 @@ fn draw_fin(f: &mut Frame, area: Rect) {
             Style::default().fg(Color::White).add_modifier(Modifier::ITALIC),
         )),
         Line::from(""),
-        Line::from("Press Ctrl+C to exit"),
+        Line::from(format!("Press {} to exit", keys.keys_label(Context::Fin, Command::Quit))),
     ];
 
     let fin = Paragraph::new(lines)
@@ -130,7 +190,7 @@ fn draw_fin(f: &mut Frame, area: Rect) {
     f.render_widget(fin, area);
 }
 
-fn draw_playing(f: &mut Frame, size: Rect, world: &World) {
+fn draw_playing(f: &mut Frame, size: Rect, world: &World, keys: &KeyMap) {
     let log_h = (size.height / 4).clamp(5, 10);
 
     let vertical = Layout::default()
@@ -156,7 +216,7 @@ fn draw_playing(f: &mut Frame, size: Rect, world: &World) {
             .split(top);
 
         draw_map(f, stacked[0], world);
-        draw_sidebar(f, stacked[1], world);
+        draw_sidebar(f, stacked[1], world, keys);
     } else {
         let horizontal = Layout::default()
             .direction(Direction::Horizontal)
@@ -167,20 +227,38 @@ fn draw_playing(f: &mut Frame, size: Rect, world: &World) {
             .split(top);
 
         draw_map(f, horizontal[0], world);
-        draw_sidebar(f, horizontal[1], world);
+        draw_sidebar(f, horizontal[1], world, keys);
     }
 
     if world.dialogue.is_some() {
         draw_dialogue(f, bottom, world);
+    } else if world.shop.is_some() {
+        draw_shop(f, bottom, world, keys);
     } else if world.stats_open {
-        draw_stats(f, bottom, world);
+        draw_stats(f, bottom, world, keys);
+    } else if world.journal_open {
+        draw_journal(f, bottom, world);
     } else {
         draw_logs(f, bottom, world);
     }
 }
 
-fn draw_battle(f: &mut Frame, size: Rect, world: &World) {
-    let log_h = (size.height / 4).clamp(5, 10);
+/// "Enemy HP: 8/20" followed by any status effects.
+fn hp_line(label: &str, hp: i32, max_hp: i32, statuses: &[StatusEffect]) -> Line<'static> {
+    const BAR_W: i32 = 10;
+    let filled = (hp.max(0) * BAR_W + max_hp - 1) / max_hp.max(1);
+    let bar = format!("[{}{}]", "#".repeat(filled as usize), "-".repeat((BAR_W - filled).max(0) as usize));
+    let mut spans = vec![Span::raw(format!("{}: {} {}/{}", label, bar, hp.max(0), max_hp))];
+    if !statuses.is_empty() {
+        spans.push(Span::styled(format!("  {}", status::describe(statuses)), Style::default().fg(Color::Magenta)));
+    }
+    Line::from(spans)
+}
+
+fn draw_battle(f: &mut Frame, size: Rect, world: &World, keys: &KeyMap) {
+    // One more row per extra enemy so the whole group fits.
+    let extra = world.battle.as_ref().map_or(0, |b| b.enemies.len().saturating_sub(1)) as u16;
+    let log_h = (size.height / 4).clamp(5, 10) + extra;
 
     let vertical = Layout::default()
         .direction(Direction::Vertical)
@@ -205,7 +283,7 @@ fn draw_battle(f: &mut Frame, size: Rect, world: &World) {
             .split(top);
 
         draw_map(f, stacked[0], world);
-        draw_sidebar(f, stacked[1], world);
+        draw_sidebar(f, stacked[1], world, keys);
     } else {
         let horizontal = Layout::default()
             .direction(Direction::Horizontal)
@@ -216,29 +294,59 @@ fn draw_battle(f: &mut Frame, size: Rect, world: &World) {
             .split(top);
 
         draw_map(f, horizontal[0], world);
-        draw_sidebar(f, horizontal[1], world);
+        draw_sidebar(f, horizontal[1], world, keys);
     }
 
     if let Some(bs) = &world.battle {
-        let mut lines = vec![
-            Line::from(Span::styled(
-                format!("BATTLE VS {}", bs.enemy_name),
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-            )),
-            Line::from(format!("Enemy HP: {}/{}", bs.enemy_hp, bs.enemy_max_hp)),
-            Line::from(""),
-        ];
-        
-        if world.inventory_open {
-             lines.push(Line::from("SELECT CONSUMABLE (Space) OR I to Cancel"));
-             for (i, c) in world.player.inventory.consumables.iter().enumerate() {
-                 let marker = if matches!(world.player.inventory.selection(), InvSelection::Consumable(idx) if idx == i) { ">" } else { " " };
-                 lines.push(Line::from(format!("{} {}", marker, c.name)));
+        let names: Vec<&str> = bs.enemies.iter().map(|e| e.name.as_str()).collect();
+        let mut lines = vec![Line::from(Span::styled(
+            format!("BATTLE VS {}", names.join(", ")),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ))];
+        for (i, e) in bs.enemies.iter().enumerate() {
+            if e.hp <= 0 {
+                lines.push(Line::from(Span::styled(format!("  {}: defeated", e.name), Style::default().fg(Color::DarkGray))));
+                continue;
+            }
+            // The marker only shows once there's a choice to make.
+            let marker = if bs.pending.is_some() && i == bs.target { ">" } else { " " };
+            let label = if bs.enemies.len() > 1 { format!("{} {}", marker, e.name) } else { "Enemy HP".to_string() };
+            lines.push(hp_line(&label, e.hp, e.max_hp, &e.statuses));
+        }
+        lines.push(hp_line("Your HP", world.player.hp, world.player.max_hp(), &world.player.statuses));
+        lines.push(Line::from(""));
+
+        if bs.pending.is_some() {
+            lines.push(Line::from(format!("CHOOSE TARGET  {}", keys.help(Context::BattleTarget).join(" | "))));
+        } else if world.inventory_open {
+             lines.push(Line::from(format!(
+                 "SELECT CONSUMABLE ({}) OR {} to Cancel",
+                 keys.keys_label(Context::BattleInventory, Command::UseItem),
+                 keys.keys_label(Context::BattleInventory, Command::Inventory),
+             )));
+             let p = &world.player;
+             for (i, stack) in p.inventory.consumables.iter().enumerate() {
+                 let selected = matches!(p.inventory.selection(), InvSelection::Consumable(idx) if idx == i);
+                 let marker = if selected { ">" } else { " " };
+                 lines.push(Line::from(format!("{} {}", marker, stack.label())));
+                 if selected {
+                     let (now, then) = (p.hp, p.hp_after(&stack.item));
+                     let max = p.max_hp();
+                     lines.push(delta_line("HP", format!("{now}/{max}"), format!("{then}/{max}"), then - now));
+                 }
              }
+        } else if bs.skills_open {
+            lines.push(Line::from(format!("SKILLS  {}", keys.help(Context::BattleSkills).join(" | "))));
+            for (i, skill) in world.known_skills().into_iter().enumerate() {
+                let marker = if i == bs.skill_cursor { ">" } else { " " };
+                let state = match world.skill_cooldown(&skill.id) {
+                    0 => skill.description.clone(),
+                    n => format!("ready in {} turn(s)", n),
+                };
+                lines.push(Line::from(format!("{} {} - {}", marker, skill.name, state)));
+            }
         } else {
-            lines.push(Line::from("1. Fight"));
-            lines.push(Line::from("2. Inventory"));
-            lines.push(Line::from("3. Run"));
+            lines.extend(keys.help(Context::Battle).into_iter().map(Line::from));
         }
         
         lines.push(Line::from("--- Log ---"));
@@ -294,23 +402,13 @@ fn draw_map(f: &mut Frame, area: Rect, world: &World) {
             }
 
             if let Some(npc) = world.npc_at(world.current, wx, wy) {
-                let (style, bold) = match npc.id {
-                    NpcId::MayorSol => (Style::default().fg(Color::Cyan), true),
-                    NpcId::Noor => (Style::default().fg(Color::Magenta), true),
-                    NpcId::Lamp | NpcId::Dorosht => (Style::default().fg(Color::Yellow), true),
-                    NpcId::Random1 | NpcId::Random2 | NpcId::Random3 => {
-                        (Style::default().fg(Color::Yellow), true)
-                    }
-                    NpcId::Weeping1 | NpcId::Weeping2 | NpcId::Weeping3 | NpcId::Weeping4 => {
-                        (Style::default().fg(Color::LightBlue), true)
-                    }
-                    NpcId::Shab | NpcId::Krad | NpcId::Mah => {
-                        (Style::default().fg(Color::Red), true)
-                    }
-                };
+                let color = npc::registry()
+                    .get(&npc.id)
+                    .map(|def| npc_color(def.color))
+                    .unwrap_or(Color::White);
                 spans.push(Span::styled(
                     npc.symbol.to_string(),
-                    if bold { style.add_modifier(Modifier::BOLD) } else { style },
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
                 ));
                 continue;
             }
@@ -341,6 +439,60 @@ fn draw_map(f: &mut Frame, area: Rect, world: &World) {
     f.render_widget(map_widget, area);
 }
 
+fn npc_color(c: NpcColor) -> Color {
+    match c {
+        NpcColor::White => Color::White,
+        NpcColor::Yellow => Color::Yellow,
+        NpcColor::Cyan => Color::Cyan,
+        NpcColor::Magenta => Color::Magenta,
+        NpcColor::LightBlue => Color::LightBlue,
+        NpcColor::Red => Color::Red,
+        NpcColor::Green => Color::Green,
+    }
+}
+
+fn rarity_color(r: Rarity) -> Color {
+    match r {
+        Rarity::Common => Color::Reset,
+        Rarity::Uncommon => Color::Green,
+        Rarity::Rare => Color::LightBlue,
+        Rarity::Epic => Color::Magenta,
+        Rarity::Legendary => Color::Yellow,
+    }
+}
+
+/// A sidebar line for a piece of equipment, coloured by its rarity.
+fn gear_line(text: String, gear: Option<&Equipment>) -> Line<'static> {
+    match gear {
+        Some(eq) => Line::from(Span::styled(text, Style::default().fg(rarity_color(eq.rarity)))),
+        None => Line::from(text),
+    }
+}
+
+/// "now -> then (+d)", the change green if it's a gain and red if it's a loss.
+fn delta_line(label: &str, now: String, then: String, delta: i32) -> Line<'static> {
+    let color = match delta {
+        d if d > 0 => Color::Green,
+        d if d < 0 => Color::Red,
+        _ => Color::DarkGray,
+    };
+    Line::from(vec![
+        Span::raw(format!("    {:<6} {} -> {} ", label, now, then)),
+        Span::styled(format!("({})", fmt_bonus(delta)), Style::default().fg(color)),
+    ])
+}
+
+/// The player's effective stats now against after a possible change.
+fn compare_lines(now: Stats, then: Stats) -> Vec<Line<'static>> {
+    [(Stat::Attack, "ATK"), (Stat::Defense, "DEF"), (Stat::Speed, "SPD"), (Stat::MaxHp, "Max HP")]
+        .into_iter()
+        .map(|(stat, label)| {
+            let (a, b) = (now.get(stat), then.get(stat));
+            delta_line(label, a.to_string(), b.to_string(), b - a)
+        })
+        .collect()
+}
+
 fn tab_label(tab: InvTab, active: InvTab, title: &str) -> Span<'static> {
     if tab == active {
         Span::styled(
@@ -355,7 +507,7 @@ fn tab_label(tab: InvTab, active: InvTab, title: &str) -> Span<'static> {
     }
 }
 
-fn draw_sidebar(f: &mut Frame, area: Rect, world: &World) {
+fn draw_sidebar(f: &mut Frame, area: Rect, world: &World, keys: &KeyMap) {
     f.render_widget(Clear, area);
 
     let p = &world.player;
@@ -366,26 +518,39 @@ fn draw_sidebar(f: &mut Frame, area: Rect, world: &World) {
         Line::from(vec![
             Span::styled("HP: ", Style::default().fg(Color::White)),
             Span::styled(
-                format!("{}/{}", p.hp, p.max_hp),
+                format!("{}/{}", p.hp, p.max_hp()),
                 Style::default().fg(Color::Green),
             ),
         ]),
         Line::from(format!("ATK: {}", p.attack())),
         Line::from(format!("DEF: {}", p.defense())),
         Line::from(format!("SPD: {}", p.speed())),
+        Line::from(format!("LVL: {}", p.exp.level)),
+        Line::from(format!("Gold: {}", p.gold)),
         Line::from(format!("Pos: ({}, {})", p.x, p.y)),
         Line::from(format!("Room: {}", room_label)),
-        Line::from(""),
     ];
+    for b in &p.buffs {
+        let mut parts = Vec::new();
+        if b.atk_bonus != 0 { parts.push(format!("{} ATK", fmt_bonus(b.atk_bonus))); }
+        if b.def_bonus != 0 { parts.push(format!("{} DEF", fmt_bonus(b.def_bonus))); }
+        if b.speed_bonus != 0 { parts.push(format!("{} SPD", fmt_bonus(b.speed_bonus))); }
+        text.push(Line::from(Span::styled(
+            format!("Boost: {} ({} turns)", parts.join(", "), b.turns),
+            Style::default().fg(Color::Magenta),
+        )));
+    }
+    text.push(Line::from(""));
 
     if world.inventory_open {
+        let use_keys = keys.keys_label(Context::Inventory, Command::UseItem);
         text.push(Line::from(Span::styled(
             "Inventory",
             Style::default().fg(Color::Cyan),
         )));
 
         text.push(Line::from(vec![
-            tab_label(InvTab::Weapons, inv.tab, "Weapons"),
+            tab_label(InvTab::Equipment, inv.tab, "Equipment"),
             Span::raw(" "),
             tab_label(InvTab::Consumables, inv.tab, "Consumables"),
             Span::raw(" "),
@@ -394,74 +559,38 @@ fn draw_sidebar(f: &mut Frame, area: Rect, world: &World) {
         text.push(Line::from(""));
 
         text.push(Line::from(Span::styled(
-            "Weapons",
+            "Equipment",
             Style::default().fg(Color::White),
         )));
 
-        let sword_marker = if inv.tab == InvTab::Weapons
-            && matches!(inv.selection(), InvSelection::SwordSlot)
-        {
-            ">"
-        } else {
-            " "
-        };
-
-        let sword_line = match &inv.sword {
-            Some(sw) => {
-                if inv.tab == InvTab::Weapons
-                    && matches!(inv.selection(), InvSelection::SwordSlot)
-                {
-                    format!(
-                        "{} Sword : {} ({} ATK, {} DEF, {} SPD, {} HP) [Space to unequip]",
-                        sword_marker,
-                        sw.name,
-                        fmt_bonus(sw.atk_bonus),
-                        fmt_bonus(sw.def_bonus),
-                        fmt_bonus(sw.speed_bonus),
-                        fmt_bonus(sw.hp_bonus),
-                    )
-                } else {
-                    format!("{} Sword : {}", sword_marker, sw.name)
-                }
-            }
-            None => format!("{} Sword : <empty>", sword_marker),
-        };
-        text.push(Line::from(sword_line));
-
-        let shield_marker = if inv.tab == InvTab::Weapons
-            && matches!(inv.selection(), InvSelection::ShieldSlot)
-        {
-            ">"
-        } else {
-            " "
-        };
-
-        let shield_line = match &inv.shield {
-            Some(sh) => {
-                if inv.tab == InvTab::Weapons
-                    && matches!(inv.selection(), InvSelection::ShieldSlot)
-                {
-                    format!(
-                        "{} Shield: {} ({} ATK, {} DEF, {} SPD, {} HP) [Space to unequip]",
-                        shield_marker,
-                        sh.name,
-                        fmt_bonus(sh.atk_bonus),
-                        fmt_bonus(sh.def_bonus),
-                        fmt_bonus(sh.speed_bonus),
-                        fmt_bonus(sh.hp_bonus),
-                    )
-                } else {
-                    format!("{} Shield: {}", shield_marker, sh.name)
-                }
-            }
-            None => format!("{} Shield: <empty>", shield_marker),
-        };
-        text.push(Line::from(shield_line));
+        for slot in GearSlot::ALL {
+            let selected = inv.tab == InvTab::Equipment
+                && matches!(inv.selection(), InvSelection::Gear(s) if s == slot);
+            let marker = if selected { ">" } else { " " };
+
+            let line = match inv.gear(slot) {
+                Some(eq) if selected => format!(
+                    "{} {:<9}: {} ({} ATK, {} DEF, {} SPD, {} HP) [{} to unequip]",
+                    marker,
+                    slot.label(),
+                    eq.name,
+                    fmt_bonus(eq.atk_bonus),
+                    fmt_bonus(eq.def_bonus),
+                    fmt_bonus(eq.speed_bonus),
+                    fmt_bonus(eq.hp_bonus),
+                    use_keys,
+                ),
+                Some(eq) => format!("{} {:<9}: {}", marker, slot.label(), eq.name),
+                None if slot == GearSlot::OffHand && inv.two_handed() => format!("{} {:<9}: <two-handed>", marker, slot.label()),
+                None => format!("{} {:<9}: <empty>", marker, slot.label()),
+            };
+            text.push(gear_line(line, inv.gear(slot)));
+        }
 
         text.push(Line::from(""));
 
         text.push(Line::from(Span::styled(
-            "Consumables (Space to use)",
+            format!("Consumables ({use_keys} to use)"),
             Style::default().fg(Color::White),
         )));
 
@@ -469,7 +598,8 @@ fn draw_sidebar(f: &mut Frame, area: Rect, world: &World) {
             let marker = if inv.tab == InvTab::Consumables { ">" } else { " " };
             text.push(Line::from(format!("{} <none>", marker)));
         } else {
-            for (i, c) in inv.consumables.iter().enumerate() {
+            for (i, stack) in inv.consumables.iter().enumerate() {
+                let c = &stack.item;
                 let selected = inv.tab == InvTab::Consumables
                     && matches!(inv.selection(), InvSelection::Consumable(idx) if idx == i);
 
@@ -477,26 +607,27 @@ fn draw_sidebar(f: &mut Frame, area: Rect, world: &World) {
 
                 if selected {
                     text.push(Line::from(format!(
-                        "{} {} ({} HP, {} ATK, {} DEF) [Space to use]",
+                        "{} {} ({} HP, {} ATK, {} DEF) [{} to use]",
                         marker,
-                        c.name,
+                        stack.label(),
                         fmt_bonus(c.heal),
                         fmt_bonus(c.atk_bonus),
                         fmt_bonus(c.def_bonus),
+                        use_keys,
                     )));
                 } else {
-                    text.push(Line::from(format!("{} {}", marker, c.name)));
+                    text.push(Line::from(format!("{} {}", marker, stack.label())));
                 }
             }
         }
 
-        let empty_slots = 10usize.saturating_sub(inv.consumables.len());
+        let empty_slots = inv.capacity().saturating_sub(inv.consumables.len());
         text.push(Line::from(format!("Empty slots: {}", empty_slots)));
 
         text.push(Line::from(""));
 
         text.push(Line::from(Span::styled(
-            "Backpack (Space to equip)",
+            format!("Backpack ({use_keys} to equip)"),
             Style::default().fg(Color::White),
         )));
 
@@ -505,35 +636,32 @@ fn draw_sidebar(f: &mut Frame, area: Rect, world: &World) {
             text.push(Line::from(format!("{} <empty>", marker)));
         } else {
             for (i, b) in inv.backpack.iter().enumerate() {
-                let marker = if inv.tab == InvTab::Backpack
-                    && matches!(inv.selection(), InvSelection::BackpackItem(idx) if idx == i)
-                {
-                    ">"
-                } else {
-                    " "
-                };
-                text.push(Line::from(format!("{} {}", marker, b.name)));
+                let selected = inv.tab == InvTab::Backpack
+                    && matches!(inv.selection(), InvSelection::BackpackItem(idx) if idx == i);
+                let marker = if selected { ">" } else { " " };
+                text.push(gear_line(format!("{} {}", marker, b.name), Some(b)));
+                if selected {
+                    let (then, removed) = p.preview_equip(b);
+                    if !removed.is_empty() {
+                        text.push(Line::from(format!("    Replaces {}", removed.join(" and "))));
+                    }
+                    text.extend(compare_lines(p.stats(), then));
+                }
             }
         }
 
         text.push(Line::from(""));
-        text.push(Line::from("Up/Down: select"));
-        text.push(Line::from("T: change tab"));
-        text.push(Line::from("Space: use/unequip/equip"));
-        text.push(Line::from("I or Esc: close"));
-        text.push(Line::from("Q: stats"));
+        text.extend(keys.help(Context::Inventory).into_iter().map(Line::from));
     } else {
         text.push(Line::from(Span::styled(
             "Controls",
             Style::default().fg(Color::Cyan),
         )));
-        text.push(Line::from("WASD / Arrows: Move"));
-        text.push(Line::from("E: Talk / Open chest"));
-        text.push(Line::from("I: Inventory"));
-        text.push(Line::from("T: Inventory Tab"));
-        text.push(Line::from("Q: Stats"));
-        text.push(Line::from("Ctrl+C: Quit"));
-        text.push(Line::from("E on +: Switch rooms"));
+        text.extend(keys.help(Context::Playing).into_iter().map(Line::from));
+        text.extend(keys.help(Context::Global).into_iter().map(Line::from));
+        if let Some(k) = keys.keys_for(Context::Playing, Command::Interact).first() {
+            text.push(Line::from(format!("{} on +: Switch rooms", k)));
+        }
     }
 
     let sidebar = Paragraph::new(text)
@@ -558,32 +686,47 @@ fn draw_logs(f: &mut Frame, area: Rect, world: &World) {
     f.render_widget(logs, area);
 }
 
-fn draw_stats(f: &mut Frame, area: Rect, world: &World) {
+fn draw_stats(f: &mut Frame, area: Rect, world: &World, keys: &KeyMap) {
     let p = &world.player;
     let inv = &p.inventory;
 
-    let sword = inv.sword.as_ref().map(|s| s.name.as_str()).unwrap_or("<empty>");
-    let shield = inv.shield.as_ref().map(|s| s.name.as_str()).unwrap_or("<empty>");
-
-    let lines = vec![
+    let exp = &p.exp;
+    let mut lines = vec![
         Line::from(Span::styled(
             "Current Stats",
             Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
-        Line::from(format!("HP  : {}/{}", p.hp, p.max_hp)),
+        Line::from(format!("Level {}  XP {}/{}", exp.level, exp.xp, xp::to_next(exp.level))),
+        Line::from(format!("HP  : {}/{}", p.hp, p.max_hp())),
         Line::from(format!("ATK : {}", p.attack())),
         Line::from(format!("DEF : {}", p.defense())),
         Line::from(format!("SPD : {}", p.speed())),
         Line::from(""),
-        Line::from(format!("Sword : {}", sword)),
-        Line::from(format!("Shield: {}", shield)),
-        Line::from(""),
-        Line::from(Span::styled(
-            "Press Q or Esc to close.",
-            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
-        )),
     ];
+    if inv.equipped.is_empty() {
+        lines.push(Line::from("Gear: <none>"));
+    }
+    for (slot, eq) in &inv.equipped {
+        lines.push(Line::from(format!("{:<9}: {}", slot.label(), eq.name)));
+    }
+    lines.push(Line::from(""));
+
+    if exp.points > 0 {
+        lines.push(Line::from(Span::styled(
+            format!("LEVEL UP! {} point(s) to spend", exp.points),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )));
+        for (i, choice) in StatChoice::ALL.into_iter().enumerate() {
+            let marker = if i == world.stat_cursor { ">" } else { " " };
+            lines.push(Line::from(format!("{} {} +{}", marker, choice.label(), choice.per_point())));
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(
+        keys.help(Context::Stats).join(" | "),
+        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+    )));
 
     let stats = Paragraph::new(lines)
         .alignment(Alignment::Center)
@@ -593,6 +736,113 @@ fn draw_stats(f: &mut Frame, area: Rect, world: &World) {
     f.render_widget(stats, area);
 }
 
+fn objective_label(obj: &Objective) -> String {
+    let name = |id: &npc::NpcId| npc::registry().get(id).map(|d| d.name.clone()).unwrap_or_else(|| "???".to_string());
+    match obj {
+        Objective::TalkTo(id) => format!("Talk to {}", name(id)),
+        Objective::Defeat(id) => format!("Defeat {}", name(id)),
+        Objective::Fetch(id) => format!("Find the {}", item::registry().name(id).unwrap_or(id.as_str())),
+    }
+}
+
+fn draw_journal(f: &mut Frame, area: Rect, world: &World) {
+    let journal = world.journal();
+    let mut lines: Vec<Line> = Vec::new();
+
+    if journal.is_empty() {
+        lines.push(Line::from("No quests yet. Listen to the townsfolk."));
+    }
+    for (def, p) in journal.iter().filter(|(_, p)| !p.completed) {
+        lines.push(Line::from(Span::styled(
+            format!("{} ({}/{})", def.title, p.stage + 1, def.stages.len()),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )));
+        let stage = &def.stages[p.stage];
+        lines.push(Line::from(stage.description.clone()));
+        for (i, obj) in stage.objectives.iter().enumerate() {
+            let done = p.objectives_done.get(i).copied().unwrap_or(false);
+            let mark = if done { "[x]" } else { "[ ]" };
+            lines.push(Line::from(format!("  {} {}", mark, objective_label(obj))));
+        }
+    }
+    for (def, _) in journal.iter().filter(|(_, p)| p.completed) {
+        lines.push(Line::from(Span::styled(
+            format!("{} (done)", def.title),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    lines.push(Line::from(Span::styled(
+        "Press J or Esc to close.",
+        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+    )));
+
+    let journal = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Journal"))
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(journal, area);
+}
+
+fn draw_shop(f: &mut Frame, area: Rect, world: &World, keys: &KeyMap) {
+    let Some(shop) = &world.shop else { return };
+    let items = item::registry();
+    let inv = &world.player.inventory;
+    let mut lines = vec![Line::from(Span::styled(
+        format!("Gold: {}", world.player.gold),
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+    ))];
+
+    match shop.mode {
+        ShopMode::Buy => {
+            for (i, id) in items.shop().iter().enumerate() {
+                let marker = if i == shop.cursor { ">" } else { " " };
+                let name = items.name(id).unwrap_or(id.as_str());
+                lines.push(Line::from(format!("{} {} - {} gold", marker, name, items.price(id).unwrap_or(0))));
+            }
+        }
+        ShopMode::Sell => {
+            lines.push(Line::from(vec![
+                tab_label(InvTab::Equipment, inv.tab, "Equipment"),
+                Span::raw(" "),
+                tab_label(InvTab::Consumables, inv.tab, "Consumables"),
+                Span::raw(" "),
+                tab_label(InvTab::Backpack, inv.tab, "Backpack"),
+            ]));
+            let offer = |id: &ItemId| match items.sell_price(id) {
+                Some(p) => format!("{} gold", p),
+                None => "won't buy".to_string(),
+            };
+            let (stock, cursor): (Vec<(&ItemId, String)>, usize) = match inv.tab {
+                InvTab::Equipment => (Vec::new(), 0),
+                InvTab::Consumables => (inv.consumables.iter().map(|s| (&s.item.id, s.label())).collect(), inv.consumable_cursor),
+                InvTab::Backpack => (inv.backpack.iter().map(|e| (&e.id, e.name.clone())).collect(), inv.backpack_cursor),
+            };
+            if inv.tab == InvTab::Equipment {
+                lines.push(Line::from("Unequip gear before selling it."));
+            } else if stock.is_empty() {
+                lines.push(Line::from("(empty)"));
+            }
+            for (i, (id, name)) in stock.into_iter().enumerate() {
+                let marker = if i == cursor { ">" } else { " " };
+                lines.push(Line::from(format!("{} {} - {}", marker, name, offer(id))));
+            }
+        }
+    }
+
+    lines.push(Line::from(Span::styled(
+        keys.help(Context::Shop).join(" | "),
+        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+    )));
+
+    let title = match shop.mode { ShopMode::Buy => "Shop - Buy", ShopMode::Sell => "Shop - Sell" };
+    let panel = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(panel, area);
+}
+
 fn draw_dialogue(f: &mut Frame, area: Rect, world: &World) {
     let d = world.dialogue.as_ref().unwrap();
     let page_text = &d.pages[d.page_index];
@@ -0,0 +1,440 @@
+use sunny_days::engine::action::Action;
+use sunny_days::engine::entity::{GearSlot, Stack};
+use sunny_days::engine::headless::Headless;
+use sunny_days::engine::item::{self, ItemId};
+use sunny_days::engine::save::{load_world, save_world};
+use sunny_days::engine::status::StatusKind;
+use sunny_days::engine::world::{Chest, GameState};
+use sunny_days::map::tile::Tile;
+
+const WANDER: [Action; 8] = [
+    Action::Move(1, 0),
+    Action::Move(1, 0),
+    Action::Move(0, 1),
+    Action::Move(-1, 0),
+    Action::Move(0, -1),
+    Action::Move(0, -1),
+    Action::ToggleInventory,
+    Action::ToggleInventory,
+];
+
+fn playing(seed: u64) -> Headless {
+    let mut game = Headless::new(seed);
+    game.run([Action::Confirm, Action::Confirm]);
+    game
+}
+
+/// Puts the player just left of an NPC and presses E.
+fn talk_to(game: &mut Headless, id: &str) {
+    let world = game.world_mut();
+    let npc = world.npcs.iter().find(|n| n.id.as_str() == id).unwrap().clone();
+    world.current = npc.room;
+    world.player.x = npc.x - 1;
+    world.player.y = npc.y;
+    game.step(Action::Interact);
+}
+
+/// Puts a closed chest holding `item` on a free tile beside the player and
+/// returns the step onto it.
+fn chest_beside(game: &mut Headless, item: &str) -> (i32, i32) {
+    let item = item::registry().new_consumable(&ItemId::new(item)).unwrap();
+    let world = game.world_mut();
+    let (px, py) = (world.player.x, world.player.y);
+    let level = &mut world.levels[world.current];
+    let (dx, dy) = [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter()
+        .find(|(dx, dy)| level.map.is_walkable((px + dx) as usize, (py + dy) as usize))
+        .unwrap();
+    level.map.set((px + dx) as usize, (py + dy) as usize, Tile::Chest);
+    level.chests.push(Chest { x: px + dx, y: py + dy, item: Some(item), weapon: None, gold: 0, opened: false });
+    (dx, dy)
+}
+
+#[test]
+fn title_and_intro_lead_into_play() {
+    let mut game = Headless::new(3);
+    assert_eq!(game.snapshot().state, GameState::Title);
+    game.step(Action::Confirm);
+    assert_eq!(game.snapshot().state, GameState::Intro);
+    game.step(Action::Confirm);
+    assert_eq!(game.snapshot().state, GameState::Playing);
+}
+
+#[test]
+fn same_seed_and_actions_give_the_same_run() {
+    let mut a = playing(99);
+    let mut b = playing(99);
+    a.run(WANDER);
+    b.run(WANDER);
+    assert_eq!(a.snapshot(), b.snapshot());
+}
+
+#[test]
+fn talking_exposes_the_dialogue_page() {
+    let mut game = playing(42);
+    talk_to(&mut game, "mayor_sol");
+
+    let snap = game.snapshot();
+    assert_eq!(snap.state, GameState::Dialogue);
+    let page = snap.dialogue.unwrap();
+    assert_eq!(page.title, "Mayor Sol");
+    assert_eq!((page.page, page.pages), (0, 2));
+
+    game.run([Action::Confirm, Action::Choice('y')]);
+    assert!(game.world().flag("mayor_done"));
+    assert!(game.snapshot().logs.iter().any(|l| l == "New quest: The Weeping"));
+}
+
+#[test]
+fn quitting_ends_the_run() {
+    let mut game = playing(5);
+    assert!(!game.step(Action::Quit));
+    assert!(!game.is_running());
+
+    let before = game.snapshot();
+    game.step(Action::Move(1, 0));
+    assert_eq!(game.snapshot(), before);
+}
+
+/// Walks up to Shab and pages through the challenge into the battle.
+fn fight_shab(seed: u64) -> Headless {
+    let mut game = playing(seed);
+    talk_to(&mut game, "shab");
+    while game.snapshot().state == GameState::Dialogue {
+        game.step(Action::Confirm);
+    }
+    assert_eq!(game.snapshot().state, GameState::Battle);
+    game
+}
+
+#[test]
+fn battles_replay_identically() {
+    let turns = [Action::BattleOption(1, false), Action::BattleOption(3, false), Action::BattleOption(1, false)];
+    let mut a = fight_shab(11);
+    let mut b = fight_shab(11);
+    a.run(turns);
+    b.run(turns);
+    assert_eq!(a.snapshot(), b.snapshot());
+}
+
+#[test]
+fn saved_battles_keep_their_rolls() {
+    let mut live = fight_shab(12);
+    live.step(Action::BattleOption(1, false));
+
+    let path = std::env::temp_dir().join(format!("sunny-days-rng-{}.json", std::process::id()));
+    save_world(live.world(), &path).unwrap();
+    let mut restored = Headless::from_world(load_world(&path).unwrap());
+    let _ = std::fs::remove_file(&path);
+
+    let turns = [Action::BattleOption(1, false); 3];
+    live.run(turns);
+    restored.run(turns);
+    assert_eq!(live.snapshot(), restored.snapshot());
+}
+
+#[test]
+fn skills_menu_uses_a_turn() {
+    let mut game = fight_shab(13);
+    game.step(Action::BattleOption(4, false));
+    assert!(game.snapshot().logs.iter().any(|l| l == "You haven't learned any skills yet."));
+
+    game.world_mut().set_flag("shab_defeated");
+    game.step(Action::BattleOption(4, false));
+    assert!(game.snapshot().battle.unwrap().skills_open);
+    game.step(Action::UseSkill);
+
+    let logs = game.snapshot().logs;
+    assert!(logs.iter().any(|l| l == "You use Power Strike!"));
+    assert!(logs.iter().any(|l| l == "You hit Shab for 24 dmg."));
+    assert!(game.world().flag("shab_defeated") && game.snapshot().battle.is_none());
+}
+
+#[test]
+fn mayor_sol_speaks_between_phases() {
+    let mut game = playing(14);
+    for flag in ["mayor_done", "dorosht_completed", "mah_defeated"] {
+        game.world_mut().set_flag(flag);
+    }
+    talk_to(&mut game, "mayor_sol");
+    while game.snapshot().state == GameState::Dialogue {
+        game.step(Action::Confirm);
+    }
+    let player = &mut game.world_mut().player;
+    player.base_attack = 25;
+    player.base_defense = 60;
+
+    while game.snapshot().state == GameState::Battle {
+        game.step(Action::BattleOption(1, false));
+    }
+    let snap = game.snapshot();
+    assert_eq!(snap.state, GameState::Dialogue);
+    assert!(snap.dialogue.unwrap().text.contains("answer to"));
+    assert!(snap.logs.iter().any(|l| l == "Mayor Sol changes tactics!"));
+
+    game.run([Action::Confirm, Action::Confirm]);
+    assert_eq!(game.snapshot().state, GameState::Battle);
+    assert_eq!(game.snapshot().battle.unwrap().enemies[0].defense, 10);
+}
+
+#[test]
+fn mah_fights_with_her_guards() {
+    let mut game = playing(18);
+    game.world_mut().set_flag("weeping_guards_down");
+    talk_to(&mut game, "mah");
+    while game.snapshot().state == GameState::Dialogue {
+        game.step(Action::Confirm);
+    }
+    let names: Vec<String> = game.snapshot().battle.unwrap().enemies.iter().map(|e| e.name.clone()).collect();
+    assert_eq!(names, ["Mah", "Weeping Guard 1", "Weeping Guard 2"]);
+    let player = &mut game.world_mut().player;
+    player.base_attack = 200;
+    player.base_defense = 100;
+
+    // Fight asks for a target first; backing out costs nothing.
+    game.run([Action::BattleOption(1, false), Action::InventoryDown, Action::Cancel]);
+    let battle = game.snapshot().battle.unwrap();
+    assert!(battle.pending.is_none() && battle.target == 1);
+    assert!(battle.enemies.iter().all(|e| e.hp == e.max_hp));
+
+    game.run([Action::BattleOption(1, false), Action::InventoryDown, Action::Confirm]);
+    assert!(game.snapshot().logs.iter().any(|l| l == "Weeping Guard 2 is defeated!"));
+    assert_eq!(game.snapshot().state, GameState::Battle);
+
+    while game.snapshot().state == GameState::Battle {
+        game.run([Action::BattleOption(1, false), Action::Confirm]);
+    }
+    assert!(game.world().flag("mah_defeated"));
+}
+
+#[test]
+fn buffs_wear_down_with_steps_not_time() {
+    let mut game = playing(16);
+    game.world_mut().player.add_temp_buff(2, 0, 0, 3);
+    game.run([Action::None, Action::ToggleInventory, Action::InventoryDown, Action::ToggleInventory, Action::None]);
+    assert_eq!(game.world().player.buffs[0].turns, 3);
+
+    let mut steps = 0;
+    while !game.world().player.buffs.is_empty() && steps < 50 {
+        game.run(WANDER);
+        steps += 1;
+    }
+    assert!(game.world().player.buffs.is_empty());
+    assert!(game.snapshot().logs.iter().any(|l| l == "A boost wore off."));
+}
+
+#[test]
+fn wins_award_xp_and_level_ups_are_spent_in_stats() {
+    let mut game = fight_shab(17);
+    game.world_mut().player.base_attack = 100;
+    game.world_mut().player.exp.xp = 10;
+    while game.snapshot().state == GameState::Battle {
+        game.step(Action::BattleOption(1, false));
+    }
+    let logs = game.snapshot().logs;
+    assert!(logs.iter().any(|l| l == "You gained 10 XP."));
+    assert!(logs.iter().any(|l| l.starts_with("Level up! You are now level 2.")));
+
+    while game.snapshot().state == GameState::Dialogue {
+        game.step(Action::Confirm);
+    }
+    let before = game.world().player.clone();
+    game.run([Action::ToggleStats, Action::InventoryUp, Action::SpendPoint, Action::SpendPoint]);
+    let player = &game.world().player;
+    assert_eq!(player.max_hp(), before.max_hp() + 10);
+    assert_eq!(player.base_attack, before.base_attack);
+    assert_eq!(player.exp.points, before.exp.points - 2);
+}
+
+#[test]
+fn tajer_sells_for_gold_and_buys_back_at_half() {
+    let mut game = playing(19);
+    game.world_mut().player.gold = 20;
+    talk_to(&mut game, "tajer");
+    game.step(Action::Choice('b'));
+    assert_eq!(game.snapshot().state, GameState::Playing);
+
+    game.run([Action::InventoryUp; 5]);
+    game.step(Action::UseConsumable); // wrapped round to the Oak Shield
+    assert!(game.snapshot().logs.iter().any(|l| l == "Oak Shield costs 35 gold; you have 20."));
+    game.run([Action::InventoryUp; 3]);
+    game.step(Action::UseConsumable);
+    assert!(game.snapshot().logs.iter().any(|l| l == "Bought Fiery ale for 6 gold."));
+    assert_eq!(game.world().player.gold, 14);
+    game.step(Action::Cancel);
+
+    talk_to(&mut game, "tajer");
+    game.step(Action::Choice('s'));
+    let last = game.world().player.inventory.consumables.len() - 1;
+    game.run(std::iter::repeat_n(Action::InventoryDown, last));
+    game.step(Action::UseConsumable);
+    assert!(game.snapshot().logs.iter().any(|l| l == "Sold Fiery ale for 3 gold."));
+    assert_eq!(game.world().player.gold, 17);
+}
+
+#[test]
+fn every_slot_counts_and_the_door_wants_sword_and_shield() {
+    let mut game = playing(21);
+    let (dx, dy) = game.world().levels[0].door;
+    let world = game.world_mut();
+    world.player.x = dx - 1;
+    world.player.y = dy;
+    for id in ["copper_ring", "copper_ring", "iron_helm", "basic_sword"] {
+        world.player.inventory.backpack.push(item::registry().new_equipment(&ItemId::new(id)).unwrap());
+    }
+    let attack = world.player.attack();
+
+    game.step(Action::Interact);
+    assert!(game.snapshot().logs.iter().any(|l| l == "Talk to the mayor and come back. Still missing: sword, shield."));
+
+    game.run([Action::ToggleInventory, Action::ToggleInvTab, Action::ToggleInvTab]);
+    game.run([Action::UseConsumable; 4]);
+    let player = &game.world().player;
+    assert!(player.inventory.backpack.is_empty());
+    assert!(player.inventory.gear(GearSlot::Ring1).is_some() && player.inventory.gear(GearSlot::Ring2).is_some());
+    assert_eq!(player.attack(), attack + 1 + 1 + 3);
+
+    game.step(Action::ToggleInventory);
+    game.step(Action::Interact);
+    assert_eq!(game.world().current, 0, "still no shield");
+    let shield = item::registry().new_equipment(&ItemId::new("basic_shield")).unwrap();
+    game.world_mut().player.equip(shield).unwrap();
+    game.step(Action::Interact);
+    assert_eq!(game.world().current, 1);
+}
+
+#[test]
+fn a_two_hander_opens_the_door_in_place_of_sword_and_shield() {
+    let mut game = playing(23);
+    let (dx, dy) = game.world().levels[0].door;
+    let world = game.world_mut();
+    world.player.x = dx - 1;
+    world.player.y = dy;
+    world.player.equip(item::registry().new_equipment(&ItemId::new("hunting_knife")).unwrap()).unwrap();
+
+    game.step(Action::Interact);
+    assert_eq!(game.snapshot().logs.last().unwrap(), "Talk to the mayor and come back. Still missing: shield.");
+
+    game.world_mut().player.equip(item::registry().new_equipment(&ItemId::new("willow_axe")).unwrap()).unwrap();
+    assert!(game.world().player.inventory.two_handed());
+    game.step(Action::Interact);
+    assert_eq!(game.world().current, 1);
+}
+
+#[test]
+fn two_handers_fill_both_hands_and_keep_max_hp_straight() {
+    let mut game = playing(22);
+    let player = &mut game.world_mut().player;
+    for id in ["basic_shield", "willow_axe"] {
+        player.inventory.backpack.push(item::registry().new_equipment(&ItemId::new(id)).unwrap());
+    }
+    let max_hp = player.max_hp();
+
+    game.run([Action::ToggleInventory, Action::ToggleInvTab, Action::ToggleInvTab]);
+    game.run([Action::UseConsumable; 2]);
+    assert_eq!(game.snapshot().logs.last().unwrap(), "Equipped main hand: Willow Axe. Took off Basic Shield.");
+    let player = &mut game.world_mut().player;
+    assert!(player.inventory.two_handed() && player.inventory.gear(GearSlot::OffHand).is_none());
+    assert_eq!(player.max_hp(), max_hp + 5);
+    player.hp = player.max_hp();
+
+    game.step(Action::UseConsumable);
+    let player = &game.world().player;
+    assert_eq!(player.inventory.gear(GearSlot::OffHand).unwrap().name, "Basic Shield");
+    assert!(player.inventory.gear(GearSlot::MainHand).is_none());
+    assert_eq!((player.max_hp(), player.hp), (max_hp, max_hp));
+    assert_eq!(player.inventory.backpack[0].name, "Willow Axe");
+}
+
+#[test]
+fn consumables_stack_and_a_full_pack_swaps_at_the_chest() {
+    let mut game = playing(25);
+    let new = |id: &str| item::registry().new_consumable(&ItemId::new(id)).unwrap();
+    let player = &mut game.world_mut().player;
+    player.inventory.consumables.clear();
+    for _ in 0..5 {
+        assert!(player.inventory.add_consumable(new("sunny_jerky")));
+    }
+    assert_eq!(player.inventory.consumables.len(), 1);
+    assert_eq!(player.inventory.consumables[0].label(), "Sunny Jerky x5");
+    while player.inventory.add_consumable(new("fiery_ale")) {}
+    assert_eq!(player.inventory.consumables.len(), 10);
+
+    let satchel = item::registry().new_equipment(&ItemId::new("leather_satchel")).unwrap();
+    player.equip(satchel).unwrap();
+    assert_eq!(player.inventory.capacity(), 14);
+    player.unequip(GearSlot::Bag);
+
+    // Only the five single items can make way; the full stacks stay put.
+    let stack = |id: &str, count| Stack { item: new(id), count };
+    player.inventory.consumables = vec![
+        stack("sunny_jerky", 5), stack("fiery_ale", 3), stack("fiery_ale", 3), stack("weeping_willow_bark", 3), stack("frozen_tears", 3),
+        stack("fiery_ale", 1), stack("weeping_willow_bark", 1), stack("frozen_tears", 1), stack("healing_salve", 1), stack("fiery_ale", 1),
+    ];
+    let units = |game: &Headless| game.world().player.inventory.consumables.iter().map(|s| s.count).sum::<u32>();
+    let carried = units(&game);
+
+    // A chest next to the player holding one more jerky than fits.
+    let room = game.world().current;
+    let (dx, dy) = chest_beside(&mut game, "sunny_jerky");
+
+    let chest = |game: &Headless| game.world().levels[room].chests.last().unwrap().clone();
+    let page = |game: &Headless| game.world().dialogue.as_ref().unwrap().pages[0].clone();
+
+    // Leaving it closes the chest again with the jerky still inside.
+    game.step(Action::Move(dx, dy));
+    assert!(page(&game).contains("(A) Swap with an existing item"));
+    game.run([Action::Choice('a'), Action::Choice('z')]);
+    assert_eq!(game.snapshot().logs.last().unwrap(), "Left the Sunny Jerky in the chest.");
+    assert!(!chest(&game).opened && chest(&game).item.is_some_and(|c| c.name == "Sunny Jerky"));
+
+    game.run([Action::Move(-dx, -dy), Action::Move(dx, dy), Action::Choice('a')]);
+    assert!(page(&game).contains("(A) Fiery ale\n(B) Weeping Willow bark\n(C) Frozen tears\n(D) Healing salve\n(N) More"));
+    assert!(!page(&game).contains(" x"), "full stacks aren't offered");
+    game.step(Action::Choice('n'));
+    assert!(page(&game).contains("(A) Fiery ale\n(N) More") && !page(&game).contains("(B)"));
+    game.step(Action::Choice('a'));
+    assert_eq!(game.snapshot().logs.last().unwrap(), "Swapped Fiery ale for Sunny Jerky; it's back in the chest.");
+    assert_eq!(game.snapshot().state, GameState::Playing);
+
+    // Nothing was destroyed: the ale waits in the chest and the pack still carries as much.
+    assert_eq!(units(&game), carried);
+    assert_eq!(game.world().player.inventory.consumables.last().unwrap().label(), "Sunny Jerky");
+    assert!(!chest(&game).opened && chest(&game).item.is_some_and(|c| c.name == "Fiery ale"));
+}
+
+#[test]
+fn a_smaller_bag_waits_until_the_pack_fits_in_it() {
+    let mut game = playing(26);
+    let satchel = item::registry().new_equipment(&ItemId::new("leather_satchel")).unwrap();
+    let mut pouch = satchel.clone();
+    pouch.name = "Pouch".to_string();
+    pouch.bag_slots = 1;
+    let player = &mut game.world_mut().player;
+    player.equip(satchel).unwrap();
+    let ale = item::registry().new_consumable(&ItemId::new("fiery_ale")).unwrap();
+    player.inventory.consumables = vec![Stack { item: ale, count: 3 }; 12];
+
+    let blocked = player.equip(pouch).unwrap_err();
+    assert_eq!(blocked.bag, "Leather Satchel");
+    player.inventory.backpack = vec![*blocked.item];
+
+    game.run([Action::ToggleInventory, Action::ToggleInvTab, Action::ToggleInvTab, Action::UseConsumable]);
+    assert_eq!(game.snapshot().logs.last().unwrap(), "Empty your Leather Satchel first.");
+    let inv = &game.world().player.inventory;
+    assert_eq!((inv.gear(GearSlot::Bag).unwrap().name.as_str(), inv.backpack[0].name.as_str()), ("Leather Satchel", "Pouch"));
+
+    game.world_mut().player.inventory.consumables.pop();
+    game.step(Action::UseConsumable);
+    assert_eq!(game.snapshot().logs.last().unwrap(), "Equipped bag: Pouch. Took off Leather Satchel.");
+    assert_eq!(game.world().player.inventory.capacity(), 11);
+}
+
+#[test]
+fn using_a_chest_item_on_the_spot_works_like_the_inventory() {
+    let mut game = playing(27);
+    let (dx, dy) = chest_beside(&mut game, "weeping_willow_bark");
+    game.run([Action::Move(dx, dy), Action::Choice('b')]);
+    assert!(game.snapshot().logs.last().unwrap().starts_with("Used Weeping Willow bark ("));
+    let statuses = &game.world().player.statuses;
+    assert!(statuses.iter().any(|s| s.kind == StatusKind::Regen && s.turns == 3), "{statuses:?}");
+}